@@ -1,57 +1,199 @@
-use std::collections::HashMap;
+use std::io::{self, BufRead};
 
-static TEST: &str = include_str!("../data/d01");
+use crate::util::{
+    checked, config,
+    extsort::{ExternalSorter, Sorted},
+    input::{self, Block},
+    parse::{self, ParseError},
+    span::span,
+    trace::{self, Event, Value},
+};
 
-fn parse_input(input: &str) -> (Vec<usize>, Vec<usize>) {
-    let mut left = Vec::new();
-    let mut right = Vec::new();
+static INPUT: &str = include_str!("../data/d01");
+static TEST: &str = include_str!("../data/d01_test");
 
-    for line in input.lines() {
-        let nums: Vec<usize> = line
-            .split_whitespace()
-            .map(|n| n.parse().unwrap())
-            .collect();
+// How many IDs of each list are sorted in memory before they are spilled to disk.
+const SORT_BUDGET: usize = 1 << 20;
 
-        assert_eq!(nums.len(), 2);
+fn input() -> &'static str {
+    input::prepare(
+        "d01",
+        config::input("d01", INPUT, TEST),
+        &[Block::Lines("0-9 ")],
+    )
+}
+
+/// Reads the pairs of IDs, one pair per line.
+fn pairs(reader: impl BufRead) -> impl Iterator<Item = io::Result<(u64, u64)>> {
+    reader.lines().enumerate().map(|(i, line)| {
+        let ids: Vec<u64> = parse::words(&line?).map_err(|e| invalid(e.at_line(i + 1)))?;
+
+        match ids[..] {
+            [left, right] => Ok((left, right)),
+            _ => Err(invalid(ParseError::new(i + 1, 1, "expected two IDs"))),
+        }
+    })
+}
+
+fn invalid(error: ParseError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Summary of the gaps between the sorted lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub pairs: usize,
+    /// The lower median if there is an even number of pairs.
+    pub median_gap: u64,
+    pub max_gap: u64,
+    /// `histogram[0]` counts gaps of 0, `histogram[k]` gaps in `2^(k-1)..2^k`.
+    pub histogram: Vec<usize>,
+}
+
+impl Event for Stats {
+    const KIND: &'static str = "lists";
 
-        left.push(nums[0]);
-        right.push(nums[1]);
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("pairs", self.pairs.into()),
+            ("median_gap", self.median_gap.into()),
+            ("max_gap", self.max_gap.into()),
+            ("histogram", self.histogram.clone().into()),
+        ]
     }
+}
 
-    (left, right)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Sum of the differences between the sorted lists.
+    pub distance: u64,
+    /// Sum of every left ID times how often it appears in the right list. Repeated left
+    /// IDs count once per repetition.
+    pub similarity: u64,
+    pub stats: Stats,
 }
 
-fn get_diff(mut left: Vec<usize>, mut right: Vec<usize>) -> usize {
-    left.sort();
-    right.sort();
+/// Compares two lists of IDs read from `reader` without holding them in memory.
+///
+/// Each list is sorted with at most `budget` IDs in memory. The distance pairs up the
+/// sorted lists, the similarity is found by merging them once more.
+pub fn reconcile(reader: impl BufRead, budget: usize) -> io::Result<Reconciliation> {
+    let mut left = ExternalSorter::new(budget);
+    let mut right = ExternalSorter::new(budget);
+
+    for pair in pairs(reader) {
+        let (a, b) = pair?;
+        left.push(a)?;
+        right.push(b)?;
+    }
+
+    let pairs = left.len();
+    let mut gaps = ExternalSorter::new(budget);
+    let mut distance = 0;
+    let mut histogram = Vec::new();
+
+    let mut left = left.finish()?;
+    let mut right = right.finish()?;
+
+    for (a, b) in (&mut left).zip(&mut right) {
+        let gap = a?.abs_diff(b?);
+        distance = checked::add(distance, gap);
+        gaps.push(gap)?;
 
-    left.into_iter()
-        .zip(right)
-        .map(|(a, b)| a.abs_diff(b))
-        .sum()
+        let bucket = (u64::BITS - gap.leading_zeros()) as usize;
+        if histogram.len() <= bucket {
+            histogram.resize(bucket + 1, 0);
+        }
+        histogram[bucket] += 1;
+    }
+
+    let mut median_gap = 0;
+    let mut max_gap = 0;
+    for (i, gap) in gaps.finish()?.enumerate() {
+        let gap = gap?;
+        if i == pairs.saturating_sub(1) / 2 {
+            median_gap = gap;
+        }
+        max_gap = gap;
+    }
+
+    left.rewind()?;
+    right.rewind()?;
+    let similarity = similarity(left, right)?;
+
+    Ok(Reconciliation {
+        distance,
+        similarity,
+        stats: Stats {
+            pairs,
+            median_gap,
+            max_gap,
+            histogram,
+        },
+    })
 }
 
-fn count_occurrences(left: &[usize], right: &[usize]) -> usize {
-    let mut occurrences: HashMap<usize, usize> = HashMap::new();
+// Walks through both sorted lists at once, counting how often each left ID is on the right.
+fn similarity(left: Sorted, mut right: Sorted) -> io::Result<u64> {
+    let mut similarity = 0;
+    let mut next_right = right.next().transpose()?;
+    // The last left ID and how often it appears in the right list.
+    let mut current: Option<(u64, u64)> = None;
+
+    for id in left {
+        let id = id?;
 
-    for n in left {
-        if occurrences.contains_key(n) {
-            continue;
+        if current.is_none_or(|(last, _)| last != id) {
+            let mut count = 0;
+            while let Some(right_id) = next_right.filter(|right_id| *right_id <= id) {
+                count += u64::from(right_id == id);
+                next_right = right.next().transpose()?;
+            }
+            current = Some((id, count));
         }
-        let counts = right.iter().filter(|other| *other == n).count();
 
-        occurrences.insert(*n, counts);
+        let count = current.map_or(0, |(_, count)| count);
+        similarity = checked::add(similarity, checked::mul(id, count));
     }
 
-    occurrences.into_iter().map(|(a, b)| a * b).sum()
+    Ok(similarity)
 }
 
-pub fn get_solution_1() -> usize {
-    let (left, right) = parse_input(TEST);
-    get_diff(left, right)
+fn reconcile_input() -> Reconciliation {
+    let _s = span("reconcile");
+    let budget = config::param("d01", "sort_budget", SORT_BUDGET);
+    reconcile(input().as_bytes(), budget).unwrap_or_else(|e| panic!("Invalid lists at {e}"))
 }
 
-pub fn get_solution_2() -> usize {
-    let (left, right) = parse_input(TEST);
-    count_occurrences(&left, &right)
+pub fn get_solution_1() -> u64 {
+    let reconciliation = reconcile_input();
+    trace::emit(|| reconciliation.stats.clone());
+    reconciliation.distance
+}
+
+pub fn get_solution_2() -> u64 {
+    reconcile_input().similarity
+}
+
+#[test]
+fn test_reconcile() {
+    let lists = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n";
+    let expected = reconcile(lists.as_bytes(), SORT_BUDGET).unwrap();
+    assert_eq!((expected.distance, expected.similarity), (11, 31));
+    assert_eq!(expected.stats.median_gap, 1);
+    assert_eq!(expected.stats.histogram, vec![1, 2, 2, 1]);
+
+    // A budget of two IDs spills every list into several runs.
+    assert_eq!(reconcile(lists.as_bytes(), 2).unwrap(), expected);
+
+    // The left 5 appears twice, so it is counted twice.
+    let repeated = reconcile("5 5\n5 1\n".as_bytes(), SORT_BUDGET).unwrap();
+    assert_eq!(repeated.similarity, 10);
+
+    // Right IDs which are not on the left are skipped while merging.
+    let gaps = reconcile("2 1\n4 2\n4 3\n7 4\n9 4\n9 9\n".as_bytes(), 2).unwrap();
+    assert_eq!(gaps.similarity, 2 + 4 * 2 * 2 + 9 * 2);
+
+    let error = reconcile("1 2\n3\n".as_bytes(), 2).unwrap_err();
+    assert_eq!(error.to_string(), "2:1: expected two IDs");
 }
@@ -1,61 +1,153 @@
-use core::ops::Range;
+use core::ops::RangeInclusive;
+
+use crate::util::{
+    config,
+    input::{self, Block},
+    parse,
+    trace::{self, Event, Value},
+};
 
 static INPUT: &str = include_str!("../data/d02");
+static TEST: &str = include_str!("../data/d02_test");
+
+fn input() -> &'static str {
+    input::prepare(
+        "d02",
+        config::input("d02", INPUT, TEST),
+        &[Block::Lines("0-9 ")],
+    )
+}
 
 fn parse_input(input: &str) -> Vec<Vec<isize>> {
-    input
-        .lines()
-        .map(|l| l.split_whitespace().map(|n| n.parse().unwrap()).collect())
-        .collect()
-}
-
-fn is_safe(report: &[isize]) -> bool {
-    if report.len() == 1 {
-        true
-    } else if report[0] < report[1] {
-        is_safe_increasing(report, -3..0)
-    } else {
-        is_safe_increasing(report, 1..4)
-    }
+    parse::lines(input, parse::words).unwrap_or_else(|e| panic!("Invalid report at {e}"))
 }
 
-fn is_safe_increasing(report: &[isize], range: Range<isize>) -> bool {
-    for (a, b) in report.iter().zip(&report[1..]) {
-        if !range.contains(&(a - b)) {
-            return false;
+/// Which reports count as safe.
+#[derive(Debug, Clone)]
+struct Rule {
+    /// Allowed differences between adjacent levels, in the direction of the report.
+    steps: RangeInclusive<isize>,
+    /// How many levels may be removed to make a report safe.
+    tolerance: usize,
+}
+
+impl Rule {
+    fn new(tolerance_key: &str, tolerance: usize) -> Self {
+        Rule {
+            steps: config::param("d02", "min_step", 1)..=config::param("d02", "max_step", 3),
+            tolerance: config::param("d02", tolerance_key, tolerance),
         }
     }
 
-    true
-}
+    /// Returns the indices of the fewest levels which have to be removed to make the
+    /// report safe, or `None` if that takes more than `tolerance` levels.
+    fn removals(&self, report: &[isize]) -> Option<Vec<usize>> {
+        [1, -1]
+            .into_iter()
+            .filter_map(|direction| self.removals_in_direction(report, direction))
+            .min_by_key(Vec::len)
+    }
 
-fn is_safe_variants(report: &[isize]) -> bool {
-    for i in 0..report.len() {
-        let variant: Vec<isize> = report
-            .iter()
-            .enumerate()
-            .filter(|(j, _)| i != *j)
-            .map(|(_, n)| *n)
-            .collect();
+    // Finds the longest chain of kept levels. Consecutive kept levels are at most
+    // `tolerance + 1` apart, so this takes O(n * tolerance) steps.
+    fn removals_in_direction(&self, report: &[isize], direction: isize) -> Option<Vec<usize>> {
+        let n = report.len();
+        let k = self.tolerance;
 
-        if is_safe(&variant) {
-            return true;
+        // For every level: the fewest removals before it if it is kept, and the kept level before it.
+        let mut best: Vec<Option<(usize, Option<usize>)>> = Vec::with_capacity(n);
+
+        for i in 0..n {
+            let mut candidate = (i <= k).then_some((i, None));
+
+            for j in i.saturating_sub(k + 1)..i {
+                let Some((removed, _)) = best[j] else {
+                    continue;
+                };
+                let removed = removed + i - j - 1;
+
+                if removed <= k
+                    && self.steps.contains(&(direction * (report[i] - report[j])))
+                    && candidate.is_none_or(|(fewest, _)| removed < fewest)
+                {
+                    candidate = Some((removed, Some(j)));
+                }
+            }
+
+            best.push(candidate);
         }
+
+        let (last, _) = (n.saturating_sub(k + 1)..n)
+            .filter_map(|i| best[i].map(|(removed, _)| (i, removed + n - 1 - i)))
+            .filter(|(_, removed)| *removed <= k)
+            .min_by_key(|(_, removed)| *removed)?;
+
+        let mut kept = vec![false; n];
+        let mut level = Some(last);
+        while let Some(i) = level {
+            kept[i] = true;
+            level = best[i].and_then(|(_, previous)| previous);
+        }
+
+        Some((0..n).filter(|i| !kept[*i]).collect())
     }
+}
 
-    false
+struct Report<'a> {
+    levels: &'a [isize],
+    removed: Option<Vec<usize>>,
 }
 
-pub fn get_solution_1() -> usize {
-    parse_input(INPUT)
+impl Event for Report<'_> {
+    const KIND: &'static str = "report";
+
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("levels", self.levels.into()),
+            ("safe", self.removed.is_some().into()),
+            ("removed", self.removed.clone().into()),
+        ]
+    }
+}
+
+fn is_safe(report: &[isize], rule: &Rule) -> bool {
+    let removed = rule.removals(report);
+    let safe = removed.is_some();
+    trace::emit(|| Report {
+        levels: report,
+        removed,
+    });
+    safe
+}
+
+fn count_safe(rule: Rule) -> usize {
+    parse_input(input())
         .into_iter()
-        .filter(|report| is_safe(report))
+        .filter(|report| is_safe(report, &rule))
         .count()
 }
 
+pub fn get_solution_1() -> usize {
+    count_safe(Rule::new("tolerance_1", 0))
+}
+
 pub fn get_solution_2() -> usize {
-    parse_input(INPUT)
-        .into_iter()
-        .filter(|report| is_safe_variants(report))
-        .count()
+    count_safe(Rule::new("tolerance_2", 1))
+}
+
+#[test]
+fn test_removals() {
+    let rule = |tolerance| Rule {
+        steps: 1..=3,
+        tolerance,
+    };
+
+    assert_eq!(rule(0).removals(&[7, 6, 4, 2, 1]), Some(vec![]));
+    assert_eq!(rule(0).removals(&[1, 3, 2, 4, 5]), None);
+    assert_eq!(rule(1).removals(&[1, 3, 9, 4, 5]), Some(vec![2]));
+    assert_eq!(rule(1).removals(&[8, 6, 4, 4, 1]), Some(vec![3]));
+    assert_eq!(rule(1).removals(&[9, 1, 2, 3, 4]), Some(vec![0]));
+    assert_eq!(rule(1).removals(&[1, 2, 7, 8, 9]), None);
+    assert_eq!(rule(2).removals(&[1, 9, 9, 2, 3]), Some(vec![1, 2]));
+    assert_eq!(rule(2).removals(&[1, 2, 3, 20, 30]), Some(vec![3, 4]));
 }
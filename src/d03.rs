@@ -1,159 +1,451 @@
-use std::iter::Peekable;
+use std::io::{self, Read};
+
+use crate::util::{
+    config,
+    input::{self, Block},
+};
 
 static DATA: &str = include_str!("../data/d03");
+static TEST: &str = include_str!("../data/d03_test");
 
-#[derive(Debug, Clone, Copy)]
-enum Instruction {
-    Mul(u32, u32),
-    Do,
-    Dont,
+/// The kind of an instruction argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arg {
+    /// One to three digits.
+    Number,
+    /// A register from `a` to `z`. The puzzle itself doesn't use registers.
+    #[allow(dead_code)]
+    Register,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum State {
-    Start,
-    Mul,
-    Do,
-    Dont,
-    Num(Option<u32>),
-}
+impl Arg {
+    fn max_len(self) -> usize {
+        match self {
+            Arg::Number => 3,
+            Arg::Register => 1,
+        }
+    }
 
-fn parse_input(input: &str) -> Vec<Instruction> {
-    let mut data = Vec::new();
+    fn parse(self, bytes: &[u8]) -> Option<(Operand, &[u8])> {
+        match self {
+            Arg::Number => {
+                let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+                if !(1..=3).contains(&digits) {
+                    return None;
+                }
 
-    for line in input.lines() {
-        let mut memory_iter = line.chars().peekable();
-        while let Some(nums) = find_multiply_instruction(&mut memory_iter) {
-            data.push(nums);
+                let value = bytes[..digits]
+                    .iter()
+                    .fold(0, |value, digit| value * 10 + u32::from(digit - b'0'));
+                Some((Operand::Number(value), &bytes[digits..]))
+            }
+            Arg::Register => match bytes.split_first()? {
+                (register @ b'a'..=b'z', rest) => Some((Operand::Register(register - b'a'), rest)),
+                _ => None,
+            },
         }
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Number(u32),
+    Register(u8),
+}
+
+type Exec = fn(&mut Machine, &[Operand]);
+
+struct Signature {
+    name: &'static str,
+    args: &'static [Arg],
+    /// Only runs while the machine is enabled.
+    gated: bool,
+    exec: Exec,
+}
+
+/// A found instruction, `op` is its index in the [`InstructionSet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Instruction {
+    op: usize,
+    args: Vec<Operand>,
+}
+
+/// The instructions a [`Scanner`] looks for, written as `name(arg,arg)`.
+#[derive(Default)]
+struct InstructionSet {
+    signatures: Vec<Signature>,
+    // The length of the longest instruction, e.g. 12 for `mul(123,456)`.
+    max_len: usize,
+}
+
+impl InstructionSet {
+    /// Adds an instruction. Earlier instructions win if several match at the same place.
+    fn register(
+        mut self,
+        name: &'static str,
+        args: &'static [Arg],
+        gated: bool,
+        exec: Exec,
+    ) -> Self {
+        let commas = args.len().saturating_sub(1);
+        let len = name.len() + 2 + args.iter().map(|arg| arg.max_len()).sum::<usize>() + commas;
+        self.max_len = self.max_len.max(len);
+        self.signatures.push(Signature {
+            name,
+            args,
+            gated,
+            exec,
+        });
+        self
+    }
+
+    /// The instruction at the start of `bytes` and its length.
+    fn instruction_at(&self, bytes: &[u8]) -> Option<(Instruction, usize)> {
+        self.signatures
+            .iter()
+            .enumerate()
+            .find_map(|(op, signature)| {
+                let mut rest = bytes.strip_prefix(signature.name.as_bytes())?;
+                rest = rest.strip_prefix(b"(")?;
 
-    data
+                let mut args = Vec::with_capacity(signature.args.len());
+                for (i, arg) in signature.args.iter().enumerate() {
+                    if i > 0 {
+                        rest = rest.strip_prefix(b",")?;
+                    }
+                    let (operand, remaining) = arg.parse(rest)?;
+                    args.push(operand);
+                    rest = remaining;
+                }
+
+                let rest = rest.strip_prefix(b")")?;
+                Some((Instruction { op, args }, bytes.len() - rest.len()))
+            })
+    }
 }
 
-fn consume<I: Iterator<Item = char>>(iter: &mut Peekable<I>, other: char) -> bool {
-    match iter.peek() {
-        Some(c) if *c == other => {
-            _ = iter.next();
-            true
+/// Only `mul(a,b)`, adding the products.
+fn multiplications() -> InstructionSet {
+    InstructionSet::default().register("mul", &[Arg::Number, Arg::Number], true, |m, args| {
+        m.accumulator += m.value(args[0]) * m.value(args[1]);
+    })
+}
+
+/// `mul(a,b)` which can be turned off with `don't()` and on again with `do()`.
+fn conditional_multiplications() -> InstructionSet {
+    multiplications()
+        .register("do", &[], false, |m, _| m.enabled = true)
+        .register("don't", &[], false, |m, _| m.enabled = false)
+}
+
+/// Runs instructions of an [`InstructionSet`].
+#[derive(Debug, Clone)]
+struct Machine {
+    enabled: bool,
+    accumulator: i64,
+    registers: [i64; 26],
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            accumulator: 0,
+            registers: [0; 26],
         }
-        _ => false,
     }
 }
 
-fn parse_num<I: Iterator<Item = char>>(iter: &mut I) -> Option<(char, u32)> {
-    let mut n = 0;
-    for c in iter {
-        if let Some(d) = c.to_digit(10) {
-            n = n * 10 + d;
-        } else {
-            return Some((c, n));
+impl Machine {
+    fn value(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Number(n) => n as i64,
+            Operand::Register(r) => self.registers[r as usize],
         }
     }
 
-    None
+    fn run(&mut self, set: &InstructionSet, instruction: &Instruction) {
+        let signature = &set.signatures[instruction.op];
+        if self.enabled || !signature.gated {
+            (signature.exec)(self, &instruction.args);
+        }
+    }
 }
 
-fn is_call<I: Iterator<Item = char>>(iter: &mut Peekable<I>) -> bool {
-    consume(iter, '(') && consume(iter, ')')
+/// Finds the instructions of a set in corrupted memory read from any source.
+///
+/// Yields every instruction with its byte offset. Only the instruction under the cursor
+/// is buffered, and no input makes it panic.
+struct Scanner<'a, R> {
+    set: &'a InstructionSet,
+    reader: R,
+    buffer: Vec<u8>,
+    // Position of the cursor in the buffer and of the buffer in the input.
+    pos: usize,
+    offset: usize,
+    eof: bool,
 }
 
-fn find_multiply_instruction<I: Iterator<Item = char>>(
-    memory_iter: &mut Peekable<I>,
-) -> Option<Instruction> {
-    let mut state = State::Start;
+impl<'a, R: Read> Scanner<'a, R> {
+    fn new(set: &'a InstructionSet, reader: R) -> Self {
+        Self {
+            set,
+            reader,
+            buffer: Vec::new(),
+            pos: 0,
+            offset: 0,
+            eof: false,
+        }
+    }
 
-    while let Some(c) = memory_iter.peek() {
-        match (c, state) {
-            (c, State::Start) => match c {
-                'm' => state = State::Mul,
-                'd' => state = State::Do,
-                _ => {
-                    memory_iter.next();
-                    continue;
+    // Reads until a whole instruction fits behind the cursor.
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0; 4096];
+
+        while !self.eof && self.buffer.len() - self.pos < self.set.max_len {
+            self.buffer.drain(..self.pos);
+            self.offset += self.pos;
+            self.pos = 0;
+
+            match self.reader.read(&mut chunk) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for Scanner<'_, R> {
+    type Item = io::Result<(usize, Instruction)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Err(e) = self.fill() {
+                // Nothing more can be read after an error.
+                self.eof = true;
+                self.pos = self.buffer.len();
+                return Some(Err(e));
+            }
+
+            let window = self.buffer.get(self.pos..)?;
+            if window.is_empty() {
+                return None;
+            }
+
+            let offset = self.offset + self.pos;
+            match self
+                .set
+                .instruction_at(&window[..window.len().min(self.set.max_len)])
+            {
+                Some((instruction, len)) => {
+                    self.pos += len;
+                    return Some(Ok((offset, instruction)));
                 }
-            },
-            (c, State::Mul) => {
-                assert_eq!(*c, 'm');
-                _ = memory_iter.next(); // consume the 'm'
-                if !(consume(memory_iter, 'u')
-                    && consume(memory_iter, 'l')
-                    && consume(memory_iter, '('))
-                {
-                    state = State::Start;
-                    continue;
+                None => self.pos += 1,
+            }
+        }
+    }
+}
+
+fn input() -> &'static str {
+    input::prepare("d03", config::input("d03", DATA, TEST), &[Block::Text])
+}
+
+fn evaluate(input: &str, set: &InstructionSet) -> i64 {
+    let mut machine = Machine::default();
+
+    for found in Scanner::new(set, input.as_bytes()) {
+        match found {
+            Ok((_, instruction)) => machine.run(set, &instruction),
+            Err(e) => panic!("Can't read memory: {e}"),
+        }
+    }
+
+    machine.accumulator
+}
+
+pub fn get_solution_1() -> i64 {
+    evaluate(input(), &multiplications())
+}
+
+pub fn get_solution_2() -> i64 {
+    evaluate(input(), &conditional_multiplications())
+}
+
+/// Checks the scanner against a backtracking matcher for the regex
+/// `mul\((\d{1,3}),(\d{1,3})\)|do\(\)|don't\(\)`, on random bytes read in random chunks.
+#[test]
+fn test_scanner_matches_regex() {
+    use crate::util::math::random_numbers;
+
+    enum Token {
+        Literal(&'static [u8]),
+        Digits,
+    }
+
+    const PATTERNS: [&[Token]; 3] = [
+        &[
+            Token::Literal(b"mul("),
+            Token::Digits,
+            Token::Literal(b","),
+            Token::Digits,
+            Token::Literal(b")"),
+        ],
+        &[Token::Literal(b"do()")],
+        &[Token::Literal(b"don't()")],
+    ];
+
+    // Returns the end of the match and the numbers matched by `\d{1,3}`.
+    fn match_tokens(
+        bytes: &[u8],
+        i: usize,
+        tokens: &[Token],
+        numbers: &mut Vec<u32>,
+    ) -> Option<usize> {
+        let Some((token, rest)) = tokens.split_first() else {
+            return Some(i);
+        };
+
+        match token {
+            Token::Literal(literal) => {
+                if bytes[i..].starts_with(literal) {
+                    match_tokens(bytes, i + literal.len(), rest, numbers)
+                } else {
+                    None
                 }
-                state = State::Num(None);
             }
-            (c, State::Do) => {
-                assert_eq!(*c, 'd');
-                _ = memory_iter.next(); // consume the 'd' (nice!)
-                if !consume(memory_iter, 'o') {
-                    state = State::Start;
-                    continue;
+            Token::Digits => (1..=3).rev().find_map(|len| {
+                let digits = std::str::from_utf8(bytes.get(i..i + len)?).ok()?;
+                if !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
                 }
-                if let Some(&'n') = memory_iter.peek() {
-                    state = State::Dont;
-                    continue;
+                numbers.push(digits.parse().ok()?);
+                let end = match_tokens(bytes, i + len, rest, numbers);
+                if end.is_none() {
+                    numbers.pop();
                 }
-                if !is_call(memory_iter) {
-                    state = State::Start;
-                    continue;
-                } else {
-                    return Some(Instruction::Do);
+                end
+            }),
+        }
+    }
+
+    fn reference(bytes: &[u8]) -> Vec<(usize, Instruction)> {
+        let mut found = Vec::new();
+        let mut i = 0;
+
+        'outer: while i < bytes.len() {
+            for (p, pattern) in PATTERNS.iter().enumerate() {
+                let mut numbers = Vec::new();
+                if let Some(end) = match_tokens(bytes, i, pattern, &mut numbers) {
+                    let args = numbers.into_iter().map(Operand::Number).collect();
+                    found.push((i, Instruction { op: p, args }));
+                    i = end;
+                    continue 'outer;
                 }
             }
-            (c, State::Dont) => {
-                assert_eq!(*c, 'n');
-                _ = memory_iter.next(); // Consume the 'n'
-                if !(consume(memory_iter, '\'')
-                    && consume(memory_iter, 't')
-                    && is_call(memory_iter))
-                {
-                    state = State::Start;
-                    continue;
+            i += 1;
+        }
+
+        found
+    }
+
+    // Hands out the input in small chunks to move instructions across buffer refills.
+    struct Chunked<'a> {
+        bytes: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.chunk.min(buf.len()).min(self.bytes.len());
+            buf[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Ok(n)
+        }
+    }
+
+    let set = conditional_multiplications();
+    let alphabet = b"mul(do)n't,0123456789x\n\xff";
+    let mut random = random_numbers(3);
+
+    for _ in 0..2_000 {
+        let len = random.next().unwrap() % 80;
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| {
+                let n = random.next().unwrap();
+                if n.is_multiple_of(10) {
+                    (n >> 8) as u8
                 } else {
-                    return Some(Instruction::Dont);
+                    alphabet[(n >> 8) as usize % alphabet.len()]
                 }
-            }
-            (c, State::Num(None)) if c.is_numeric() => match parse_num(memory_iter) {
-                Some((',', lhs)) => state = State::Num(Some(lhs)),
-                _ => state = State::Start,
-            },
-            (c, State::Num(Some(lhs))) if c.is_numeric() => match parse_num(memory_iter) {
-                Some((')', rhs)) => return Some(Instruction::Mul(lhs, rhs)),
-                _ => state = State::Start,
+            })
+            .collect();
+        let chunk = 1 + random.next().unwrap() as usize % 16;
+
+        let scanned: Vec<_> = Scanner::new(
+            &set,
+            Chunked {
+                bytes: &bytes,
+                chunk,
             },
-            _ => {
-                unreachable!()
-            }
-        }
+        )
+        .map(Result::unwrap)
+        .collect();
+        assert_eq!(
+            scanned,
+            reference(&bytes),
+            "{:?}",
+            String::from_utf8_lossy(&bytes)
+        );
     }
-    None
-}
 
-pub fn get_solution_1() -> u32 {
-    parse_input(DATA)
-        .iter()
-        .map(|instruction| match instruction {
-            Instruction::Mul(lhs, rhs) => lhs * rhs,
-            _ => 0,
-        })
-        .sum()
+    let scanned: Vec<_> = Scanner::new(&set, &b"mul(1234,5)mul(x,1)mul(12,3)don't()"[..])
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        scanned,
+        [
+            (
+                19,
+                Instruction {
+                    op: 0,
+                    args: vec![Operand::Number(12), Operand::Number(3)],
+                }
+            ),
+            (
+                28,
+                Instruction {
+                    op: 2,
+                    args: vec![]
+                }
+            )
+        ]
+    );
 }
 
-pub fn get_solution_2() -> u32 {
-    let mut do_mul = true;
-    let mut sum = 0;
-
-    for instruction in parse_input(DATA) {
-        match instruction {
-            Instruction::Mul(lhs, rhs) if do_mul => sum += lhs * rhs,
-            Instruction::Do => do_mul = true,
-            Instruction::Dont => do_mul = false,
-            _ => (),
-        }
-    }
+#[test]
+fn test_custom_instructions() {
+    let set = conditional_multiplications()
+        .register("add", &[Arg::Number, Arg::Number], true, |m, args| {
+            m.accumulator += m.value(args[0]) + m.value(args[1]);
+        })
+        .register("addr", &[Arg::Register], true, |m, args| {
+            m.accumulator += m.value(args[0]);
+        })
+        .register("neg", &[Arg::Number], true, |m, args| {
+            m.accumulator -= m.value(args[0]);
+        })
+        .register("set", &[Arg::Register, Arg::Number], true, |m, args| {
+            if let Operand::Register(r) = args[0] {
+                m.registers[r as usize] = m.value(args[1]);
+            }
+        });
 
-    sum
+    let memory = "add(2,3)xneg(4)set(a,7)addr(a)mul(a,2)don't()add(1,1)do()set(A,1)neg(10)";
+    assert_eq!(evaluate(memory, &set), 5 - 4 + 7 - 10);
+    assert_eq!(evaluate(memory, &multiplications()), 0);
 }
@@ -1,101 +1,282 @@
+use std::str::FromStr;
+
+use crate::util::{
+    config,
+    input::{self, Block},
+    parse,
+};
+
 static DATA: &str = include_str!("../data/d04");
+static TEST: &str = include_str!("../data/d04_test");
 
-const X_MAS_1: &str = "MSAMS";
-const X_MAS_2: &str = "MMASS";
-const X_MAS_3: &str = "SMASM";
-const X_MAS_4: &str = "SSAMM";
+/// Letters at offsets from the anchor of a pattern.
+type Cells = Vec<(isize, isize, u8)>;
 
-trait Vec2D {
-    fn get_at(&self, row: usize, col: usize) -> Option<char>;
+#[derive(Debug, Clone)]
+struct Pattern {
+    cells: Cells,
 }
 
-impl Vec2D for [Vec<char>] {
-    fn get_at(&self, row: usize, col: usize) -> Option<char> {
-        self.get(row).and_then(|r| r.get(col)).copied()
+impl Pattern {
+    /// A word written from left to right.
+    fn word(word: &str) -> Self {
+        Pattern {
+            cells: word
+                .bytes()
+                .enumerate()
+                .map(|(col, letter)| (0, col as isize, letter))
+                .collect(),
+        }
+    }
+
+    /// A block of rows in which `.` matches any letter.
+    fn stencil(rows: &[&str]) -> Self {
+        let mut cells = Vec::new();
+
+        for (row, line) in rows.iter().enumerate() {
+            for (col, letter) in line.bytes().enumerate() {
+                if letter != b'.' {
+                    cells.push((row as isize, col as isize, letter));
+                }
+            }
+        }
+
+        Pattern { cells }
     }
 }
 
-fn parse_input(input: &str) -> Vec<Vec<char>> {
-    input.lines().map(|l| l.chars().collect()).collect()
+/// Where one step along a row and one step along a column of the pattern go in the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Orientation {
+    row_step: (isize, isize),
+    col_step: (isize, isize),
 }
 
-fn count_xmas(text: Vec<Vec<char>>) -> usize {
-    let mut count = 0;
+impl Orientation {
+    const IDENTITY: Self = Orientation {
+        row_step: (1, 0),
+        col_step: (0, 1),
+    };
 
-    for (row, line) in text.iter().enumerate() {
-        for (col, letter) in line.iter().enumerate() {
-            if *letter == 'X' {
-                count += find_xmas(&text, row, col);
-            }
+    const MIRRORED: Self = Orientation {
+        row_step: (1, 0),
+        col_step: (0, -1),
+    };
+
+    // A quarter turn clockwise.
+    fn rotated(self) -> Self {
+        let turn = |(row, col): (isize, isize)| (col, -row);
+        Orientation {
+            row_step: turn(self.row_step),
+            col_step: turn(self.col_step),
         }
     }
 
-    count
+    fn apply(self, (row, col): (isize, isize)) -> (isize, isize) {
+        (
+            row * self.row_step.0 + col * self.col_step.0,
+            row * self.row_step.1 + col * self.col_step.1,
+        )
+    }
 }
 
-fn find_xmas(text: &[Vec<char>], row: usize, col: usize) -> usize {
-    assert_eq!(text.get_at(row, col), Some('X'));
-    // up:
-    let up: String = (0..4)
-        .filter_map(|i| text.get_at(row, col.wrapping_sub(i)))
-        .collect();
-    let right: String = (0..4).filter_map(|i| text.get_at(row + i, col)).collect();
-    let down: String = (0..4).filter_map(|i| text.get_at(row, col + i)).collect();
-    let left: String = (0..4)
-        .filter_map(|i| text.get_at(row.wrapping_sub(i), col))
-        .collect();
-    let up_right = (0..4)
-        .filter_map(|i| text.get_at(row + i, col.wrapping_sub(i)))
-        .collect();
-    let down_right: String = (0..4)
-        .filter_map(|i| text.get_at(row + i, col + i))
-        .collect();
-    let down_left: String = (0..4)
-        .filter_map(|i| text.get_at(row.wrapping_sub(i), col + i))
-        .collect();
-    let up_left: String = (0..4)
-        .filter_map(|i| text.get_at(row.wrapping_sub(i), col.wrapping_sub(i)))
-        .collect();
+/// Which orientations of a pattern are searched for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Symmetry {
+    Fixed,
+    /// The four quarter turns.
+    Rotations,
+    /// The quarter turns of the pattern and of its mirror image.
+    RotationsAndReflections,
+    /// Rows running in any of the eight directions, meant for words.
+    Directions,
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
 
-    [
-        up, right, down, left, up_right, down_right, down_left, up_left,
-    ]
-    .into_iter()
-    .filter(|s| s.as_str() == "XMAS")
-    .count()
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(Symmetry::Fixed),
+            "rotations" => Ok(Symmetry::Rotations),
+            "rotations_and_reflections" => Ok(Symmetry::RotationsAndReflections),
+            "directions" => Ok(Symmetry::Directions),
+            _ => Err(format!("unknown symmetry `{s}`")),
+        }
+    }
 }
 
-fn count_x_mas(text: Vec<Vec<char>>) -> usize {
-    let mut count = 0;
+impl Symmetry {
+    fn orientations(self) -> Vec<Orientation> {
+        let turns =
+            |start: Orientation| std::iter::successors(Some(start), |o| Some(o.rotated())).take(4);
 
-    for (row, line) in text.iter().enumerate() {
-        for col in 0..line.len() {
-            if is_x_mas(&text, row, col) {
-                count += 1;
+        match self {
+            Symmetry::Fixed => vec![Orientation::IDENTITY],
+            Symmetry::Rotations => turns(Orientation::IDENTITY).collect(),
+            Symmetry::RotationsAndReflections => turns(Orientation::IDENTITY)
+                .chain(turns(Orientation::MIRRORED))
+                .collect(),
+            Symmetry::Directions => {
+                let diagonal = Orientation {
+                    row_step: (1, -1),
+                    col_step: (1, 1),
+                };
+                turns(Orientation::IDENTITY)
+                    .chain(turns(diagonal))
+                    .collect()
             }
         }
     }
+}
 
-    count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Match {
+    /// Where the anchor of the pattern is, e.g. the first letter of a word.
+    row: usize,
+    col: usize,
+    orientation: Orientation,
 }
 
-fn is_x_mas(text: &[Vec<char>], row: usize, col: usize) -> bool {
-    let indices = [(0, 0), (0, 2), (1, 1), (2, 0), (2, 2)];
-    let maybe_x_mas: String = indices
-        .iter()
-        .filter_map(|(r, c)| text.get_at(row + r, col + c))
-        .collect();
+struct WordSearch {
+    letters: Vec<u8>,
+    width: usize,
+    height: usize,
+}
 
-    maybe_x_mas.as_str() == X_MAS_1
-        || maybe_x_mas.as_str() == X_MAS_2
-        || maybe_x_mas.as_str() == X_MAS_3
-        || maybe_x_mas.as_str() == X_MAS_4
+impl WordSearch {
+    fn get(&self, row: isize, col: isize) -> Option<u8> {
+        (0..self.height as isize).contains(&row).then_some(())?;
+        (0..self.width as isize).contains(&col).then_some(())?;
+        Some(self.letters[row as usize * self.width + col as usize])
+    }
+
+    /// Returns every placement of `pattern` in the orientations allowed by `symmetry`.
+    /// Orientations count separately even if they give the same shape, e.g. a palindrome
+    /// read forwards and backwards.
+    fn find(&self, pattern: &Pattern, symmetry: Symmetry) -> Vec<Match> {
+        let placements: Vec<(Orientation, Cells)> = symmetry
+            .orientations()
+            .into_iter()
+            .map(|orientation| {
+                let cells = pattern
+                    .cells
+                    .iter()
+                    .map(|&(row, col, letter)| {
+                        let (row, col) = orientation.apply((row, col));
+                        (row, col, letter)
+                    })
+                    .collect();
+                (orientation, cells)
+            })
+            .collect();
+
+        let mut matches = Vec::new();
+
+        for row in 0..self.height as isize {
+            for col in 0..self.width as isize {
+                for (orientation, cells) in &placements {
+                    let found = cells
+                        .iter()
+                        .all(|&(dr, dc, letter)| self.get(row + dr, col + dc) == Some(letter));
+
+                    if found {
+                        matches.push(Match {
+                            row: row as usize,
+                            col: col as usize,
+                            orientation: *orientation,
+                        });
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+// Any capital letter, since the word and the stencil can be configured.
+const FORMAT: [Block; 1] = [Block::Grid("A-Z")];
+
+fn input() -> &'static str {
+    input::prepare("d04", config::input("d04", DATA, TEST), &FORMAT)
+}
+
+fn parse_input(input: &str) -> WordSearch {
+    let (letters, width, height) = parse::grid(input, |c| u8::try_from(c).ok())
+        .unwrap_or_else(|e| panic!("Invalid word search at {e}"));
+
+    WordSearch {
+        letters,
+        width,
+        height,
+    }
 }
 
 pub fn get_solution_1() -> usize {
-    count_xmas(parse_input(DATA))
+    let word = config::param("d04", "word", "XMAS".to_string());
+    let symmetry = config::param("d04", "symmetry_1", Symmetry::Directions);
+    parse_input(input())
+        .find(&Pattern::word(&word), symmetry)
+        .len()
 }
 
 pub fn get_solution_2() -> usize {
-    count_x_mas(parse_input(DATA))
+    // Rows of the stencil are separated by `/`.
+    let stencil = config::param("d04", "stencil", "M.S/.A./M.S".to_string());
+    let rows: Vec<&str> = stencil.split('/').collect();
+    let symmetry = config::param("d04", "symmetry_2", Symmetry::Rotations);
+    parse_input(input())
+        .find(&Pattern::stencil(&rows), symmetry)
+        .len()
+}
+
+#[test]
+fn test_find() {
+    let search = parse_input("XMAS\nMMAS\nAAAA\nSSSS\n");
+
+    let words = search.find(&Pattern::word("XMAS"), Symmetry::Directions);
+    let directions: Vec<_> = words
+        .iter()
+        .map(|m| (m.row, m.col, m.orientation.col_step))
+        .collect();
+    assert_eq!(directions, [(0, 0, (0, 1)), (0, 0, (1, 0)), (0, 0, (1, 1))]);
+    assert_eq!(
+        search.find(&Pattern::word("XMAS"), Symmetry::Fixed).len(),
+        1
+    );
+
+    let search = parse_input("SXM\nXAX\nSXM\n");
+    let x_mas = Pattern::stencil(&["M.S", ".A.", "M.S"]);
+    let found = search.find(&x_mas, Symmetry::Rotations);
+    assert_eq!(found.len(), 1);
+    assert_eq!((found[0].row, found[0].col), (2, 2));
+    assert_eq!(
+        found[0].orientation,
+        Orientation::IDENTITY.rotated().rotated()
+    );
+
+    // The mirror image is the stencil turned by half a turn, so it matches twice.
+    let reflected = search.find(&x_mas, Symmetry::RotationsAndReflections);
+    assert_eq!(reflected.len(), 2);
+    assert!(reflected.contains(&found[0]));
+
+    // A palindrome reads the same both ways, but every direction is a match of its own.
+    let search = parse_input("ABA\n");
+    let found = search.find(&Pattern::word("ABA"), Symmetry::Directions);
+    let positions: Vec<_> = found.iter().map(|m| (m.row, m.col)).collect();
+    assert_eq!(positions, [(0, 0), (0, 2)]);
+    assert_ne!(found[0].orientation, found[1].orientation);
+}
+
+#[test]
+fn test_other_word() {
+    let grid = "CATS\nOXAZ\nTACT\n";
+    assert!(input::lint(grid, &FORMAT).is_empty());
+
+    let search = parse_input(grid);
+    let found = search.find(&Pattern::word("CAT"), Symmetry::Directions);
+    let positions: Vec<_> = found.iter().map(|m| (m.row, m.col)).collect();
+    // Left and up from the second C.
+    assert_eq!(positions, [(0, 0), (2, 2), (2, 2)]);
 }
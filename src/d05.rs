@@ -1,104 +1,494 @@
-use std::collections::HashMap;
+use std::{cmp::Reverse, collections::BinaryHeap, fmt::Display};
+
+use crate::util::{
+    config,
+    hash::{FastMap, FastSet},
+    input::{self, Block},
+    parse,
+    trace::{self, Event, Value},
+};
 
 static INPUT: &str = include_str!("../data/d05");
+static TEST: &str = include_str!("../data/d05_test");
 
-type Data = (HashMap<usize, Vec<usize>>, Vec<Vec<usize>>);
+/// The page ordering rules, `a|b` meaning `a` has to be printed before `b`.
+#[derive(Debug, Default)]
+struct Rules {
+    after: FastMap<usize, Vec<usize>>,
+    pairs: FastSet<(usize, usize)>,
+}
 
-fn get_pair(line: &str) -> Option<(usize, usize)> {
-    if let Some(delim) = line.find('|') {
-        let from = line[0..delim].parse();
-        let to = line[delim + 1..line.len()].parse();
-        match (from, to) {
-            (Ok(f), Ok(t)) => Some((f, t)),
-            _ => None,
+impl Rules {
+    fn add(&mut self, before: usize, after: usize) {
+        if self.pairs.insert((before, after)) {
+            self.after.entry(before).or_default().push(after);
         }
-    } else {
-        None
     }
-}
 
-fn parse(input: &str) -> Data {
-    let mut graph = HashMap::new();
-    let mut lines = input.lines();
+    /// Returns the rules `a|b` which the update breaks by printing `b` before `a`.
+    fn violations(&self, update: &[usize]) -> Vec<(usize, usize)> {
+        let mut violated = Vec::new();
 
-    for line in &mut lines {
-        if let Some((from, to)) = get_pair(line) {
-            graph.entry(from).or_insert(Vec::new()).push(to);
-        } else {
-            assert_eq!("", line);
-            break;
+        for (i, &first) in update.iter().enumerate() {
+            for &second in &update[i + 1..] {
+                if self.pairs.contains(&(second, first)) {
+                    violated.push((second, first));
+                }
+            }
         }
+
+        violated
     }
 
-    let lists = lines
-        .map(|list| list.split(',').filter_map(|n| n.parse().ok()).collect())
-        .collect();
+    /// Sorts the pages of an update with only the rules between them (Kahn's algorithm).
+    ///
+    /// Of the pages which could come next, the one earlier in the update is taken, so a
+    /// correct update stays as it is. A page printed more than once is kept as often, each
+    /// copy following the rules on its own. Fails with the pages left over if the rules
+    /// between them form a cycle.
+    fn sort(&self, update: &[usize]) -> Result<Sorted, Vec<usize>> {
+        // Positions instead of pages are the nodes, so repeated pages don't share counts.
+        let mut next = vec![Vec::new(); update.len()];
+        let mut incoming = vec![0; update.len()];
 
-    (graph, lists)
-}
+        for (i, before) in update.iter().enumerate() {
+            for (j, after) in update.iter().enumerate() {
+                if i != j && self.pairs.contains(&(*before, *after)) {
+                    next[i].push(j);
+                    incoming[j] += 1;
+                }
+            }
+        }
 
-fn is_ordered(graph: &HashMap<usize, Vec<usize>>, list: &[usize]) -> bool {
-    assert!(!list.is_empty());
-    // do a search through the graph.
-    // if a page can be found, it is in order.
-    // if not, it is not in order
-    let mut frontier = vec![list[0]];
-    let mut cur = 1;
-    let mut ordered = false;
-
-    while let Some(node) = frontier.pop() {
-        if let Some(neighbors) = graph.get(&node) {
-            if neighbors.contains(&list[cur]) {
-                frontier.push(list[cur]);
-                cur += 1;
-                if cur == list.len() {
-                    ordered = true;
-                    break;
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..update.len())
+            .filter(|i| incoming[*i] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(update.len());
+        let mut ambiguous = false;
+
+        while let Some(Reverse(i)) = ready.pop() {
+            // Swapping two copies of the same page gives the same order.
+            ambiguous |= ready.iter().any(|Reverse(j)| update[*j] != update[i]);
+            order.push(update[i]);
+
+            for &j in &next[i] {
+                incoming[j] -= 1;
+                if incoming[j] == 0 {
+                    ready.push(Reverse(j));
                 }
             }
         }
+
+        if order.len() < update.len() {
+            return Err((0..update.len())
+                .filter(|i| incoming[*i] > 0)
+                .map(|i| update[i])
+                .collect());
+        }
+
+        Ok(Sorted { order, ambiguous })
     }
+}
 
-    ordered
+/// Findings about the rule set as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    rules: usize,
+    /// Groups of pages which all have to come before each other.
+    cycles: Vec<Vec<usize>>,
+    /// Rules between two pages of the same cycle, which the reduction leaves alone.
+    in_cycles: Vec<(usize, usize)>,
+    /// The fewest rules between different cycles and pages giving the same order.
+    reduction: Vec<(usize, usize)>,
+    /// Rules between different cycles and pages which follow from the others.
+    redundant: Vec<(usize, usize)>,
+    /// Pages in updates which no rule mentions.
+    unconstrained: Vec<usize>,
 }
 
-fn sort(graph: &HashMap<usize, Vec<usize>>, mut list: Vec<usize>) -> Vec<usize> {
-    assert!(!is_ordered(graph, &list));
+impl Rules {
+    fn pages(&self) -> Vec<usize> {
+        let pages: FastSet<usize> = self.pairs.iter().flat_map(|(a, b)| [*a, *b]).collect();
+        let mut pages: Vec<usize> = pages.into_iter().collect();
+        pages.sort_unstable();
+        pages
+    }
+
+    fn successors(&self, page: usize) -> &[usize] {
+        self.after.get(&page).map_or(&[][..], Vec::as_slice)
+    }
+
+    // Strongly connected components, each sorted and in order of their smallest page (Tarjan).
+    // The depth-first search keeps its own stack, so long chains of rules can't overflow.
+    fn components(&self) -> Vec<Vec<usize>> {
+        // The index and lowest reachable index of every visited page.
+        let mut index: FastMap<usize, (usize, usize)> = FastMap::default();
+        let mut stack = Vec::new();
+        let mut on_stack = FastSet::default();
+        let mut components = Vec::new();
+
+        for root in self.pages() {
+            if index.contains_key(&root) {
+                continue;
+            }
+
+            // The pages being visited with the number of successors already looked at.
+            let mut path = vec![(root, 0)];
+            let visited = index.len();
+            index.insert(root, (visited, visited));
+            stack.push(root);
+            on_stack.insert(root);
+
+            while let Some(&(page, done)) = path.last() {
+                if let Some(&next) = self.successors(page).get(done) {
+                    path.last_mut().unwrap().1 += 1;
+
+                    match index.get(&next) {
+                        None => {
+                            let visited = index.len();
+                            index.insert(next, (visited, visited));
+                            stack.push(next);
+                            on_stack.insert(next);
+                            path.push((next, 0));
+                        }
+                        Some(&(next_index, _)) if on_stack.contains(&next) => {
+                            let low = &mut index.get_mut(&page).unwrap().1;
+                            *low = (*low).min(next_index);
+                        }
+                        _ => (),
+                    }
+                    continue;
+                }
+
+                path.pop();
+                let (page_index, low) = index[&page];
+
+                if low == page_index {
+                    let mut component = Vec::new();
+                    while let Some(other) = stack.pop() {
+                        on_stack.remove(&other);
+                        component.push(other);
+                        if other == page {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+
+                if let Some(&(parent, _)) = path.last() {
+                    let parent_low = &mut index.get_mut(&parent).unwrap().1;
+                    *parent_low = (*parent_low).min(low);
+                }
+            }
+        }
+
+        for component in &mut components {
+            component.sort_unstable();
+        }
+        components.sort();
+        components
+    }
+
+    // A component is a cycle if it has more than one page or a rule `a|a`.
+    fn is_cycle(&self, component: &[usize]) -> bool {
+        component.len() > 1 || self.pairs.contains(&(component[0], component[0]))
+    }
+
+    // Shrinking every component to a single node leaves a graph without cycles, the
+    // condensation. An edge between two components is redundant if the second can also
+    // be reached from the first over other components. Of several rules between the same
+    // two components only the first one is needed. Returns the rules inside components,
+    // the reduction and the redundant rules.
+    fn reduce(&self, components: &[Vec<usize>]) -> [Vec<(usize, usize)>; 3] {
+        let component: FastMap<usize, usize> = components
+            .iter()
+            .enumerate()
+            .flat_map(|(i, pages)| pages.iter().map(move |page| (*page, i)))
+            .collect();
+
+        let mut rules: Vec<(usize, usize)> = self.pairs.iter().copied().collect();
+        rules.sort_unstable();
+
+        let mut successors: Vec<FastSet<usize>> = vec![FastSet::default(); components.len()];
+        for (before, after) in &rules {
+            let (from, to) = (component[before], component[after]);
+            if from != to {
+                successors[from].insert(to);
+            }
+        }
+
+        let mut redundant_edges = FastSet::default();
+        for (from, next) in successors.iter().enumerate() {
+            let mut reached = FastSet::default();
+            let mut frontier: Vec<usize> = next
+                .iter()
+                .flat_map(|to| &successors[*to])
+                .copied()
+                .collect();
+
+            while let Some(other) = frontier.pop() {
+                if reached.insert(other) {
+                    frontier.extend(&successors[other]);
+                }
+            }
+
+            redundant_edges.extend(next.intersection(&reached).map(|to| (from, *to)));
+        }
+
+        let [mut in_cycles, mut reduction, mut redundant] = [Vec::new(), Vec::new(), Vec::new()];
+        let mut kept = FastSet::default();
+
+        for rule in rules {
+            let edge = (component[&rule.0], component[&rule.1]);
+            if edge.0 == edge.1 {
+                in_cycles.push(rule);
+            } else if !redundant_edges.contains(&edge) && kept.insert(edge) {
+                reduction.push(rule);
+            } else {
+                redundant.push(rule);
+            }
+        }
+
+        [in_cycles, reduction, redundant]
+    }
+
+    fn analyze(&self, updates: &[Vec<usize>]) -> Analysis {
+        let components = self.components();
+        let [in_cycles, reduction, redundant] = self.reduce(&components);
+        let cycles = components
+            .into_iter()
+            .filter(|component| self.is_cycle(component))
+            .collect();
+
+        let pages: FastSet<usize> = self.pages().into_iter().collect();
+        let mut unconstrained: Vec<usize> = updates
+            .iter()
+            .flatten()
+            .filter(|page| !pages.contains(page))
+            .copied()
+            .collect::<FastSet<usize>>()
+            .into_iter()
+            .collect();
+        unconstrained.sort_unstable();
+
+        Analysis {
+            rules: self.pairs.len(),
+            cycles,
+            in_cycles,
+            reduction,
+            redundant,
+            unconstrained,
+        }
+    }
+}
 
-    // Check if two elements are in right order.
-    // If not, swap them.
-    // I have no idea why this works, i was just guessing when implementing it
-    for i in 0..list.len() - 1 {
-        for j in i..list.len() {
-            let (a, b) = (list[i], list[j]);
-            if let Some(true) = graph.get(&b).map(|ns| ns.contains(&a)) {
-                list[i] = b;
-                list[j] = a;
+impl Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn write_list(f: &mut std::fmt::Formatter<'_>, items: Vec<String>) -> std::fmt::Result {
+            if items.is_empty() {
+                Ok(())
+            } else {
+                writeln!(f, "  {}", items.join(" "))
             }
         }
+
+        let rules =
+            |rules: &[(usize, usize)]| rules.iter().map(|(a, b)| format!("{a}|{b}")).collect();
+        let pages = |pages: &[usize]| pages.iter().map(usize::to_string).collect();
+
+        writeln!(f, "rules: {}", self.rules)?;
+
+        writeln!(f, "cycles: {}", self.cycles.len())?;
+        for cycle in &self.cycles {
+            write_list(f, pages(cycle))?;
+        }
+
+        writeln!(f, "rules inside cycles: {}", self.in_cycles.len())?;
+        write_list(f, rules(&self.in_cycles))?;
+        writeln!(f, "redundant rules: {}", self.redundant.len())?;
+        write_list(f, rules(&self.redundant))?;
+        writeln!(f, "transitive reduction: {} rules", self.reduction.len())?;
+        write_list(f, rules(&self.reduction))?;
+
+        writeln!(f, "unconstrained pages: {}", self.unconstrained.len())?;
+        write_list(f, pages(&self.unconstrained))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Sorted {
+    order: Vec<usize>,
+    /// Whether the rules allow more than one order.
+    ambiguous: bool,
+}
+
+struct Update<'a> {
+    pages: &'a [usize],
+    violated: &'a [(usize, usize)],
+    sorted: &'a Sorted,
+}
+
+impl Event for Update<'_> {
+    const KIND: &'static str = "update";
+
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let violated: Vec<String> = self
+            .violated
+            .iter()
+            .map(|(before, after)| format!("{before}|{after}"))
+            .collect();
+
+        vec![
+            ("pages", self.pages.into()),
+            ("violated", violated.into()),
+            ("sorted", self.sorted.order.as_slice().into()),
+            ("ambiguous", self.sorted.ambiguous.into()),
+        ]
+    }
+}
+
+fn input() -> &'static str {
+    input::prepare(
+        "d05",
+        config::input("d05", INPUT, TEST),
+        &[Block::Lines("0-9|"), Block::Lines("0-9,")],
+    )
+}
+
+fn parse(input: &str) -> (Rules, Vec<Vec<usize>>) {
+    let [rules, updates] = parse::sections(input)[..] else {
+        panic!("Input has to consist of rules and updates");
+    };
+
+    let mut graph = Rules::default();
+
+    for rule in rules
+        .lines(|line| parse::list::<usize>(line, '|'))
+        .unwrap_or_else(|e| panic!("Invalid rule at {e}"))
+    {
+        let [from, to] = rule[..] else {
+            panic!("A rule has to consist of two pages");
+        };
+        graph.add(from, to);
     }
 
-    assert!(is_ordered(graph, &list));
-    list
+    let lists = updates
+        .lines(|line| parse::list(line, ','))
+        .unwrap_or_else(|e| panic!("Invalid update at {e}"));
+
+    (graph, lists)
+}
+
+// Returns the middle page of the correctly ordered update and whether the update was correct.
+fn check(rules: &Rules, update: &[usize]) -> (usize, bool) {
+    let violated = rules.violations(update);
+    let sorted = rules
+        .sort(update)
+        .unwrap_or_else(|pages| panic!("The rules for pages {pages:?} form a cycle"));
+
+    trace::emit(|| Update {
+        pages: update,
+        violated: &violated,
+        sorted: &sorted,
+    });
+
+    (sorted.order[sorted.order.len() / 2], violated.is_empty())
 }
 
 pub fn get_solution_1() -> usize {
-    let (graph, lists) = parse(INPUT);
+    let (rules, updates) = parse(input());
 
-    lists
+    updates
         .iter()
-        .filter(|list| is_ordered(&graph, list))
-        .map(|list| list[list.len() / 2])
+        .map(|update| check(&rules, update))
+        .filter(|(_, correct)| *correct)
+        .map(|(middle, _)| middle)
         .sum()
 }
 
 pub fn get_solution_2() -> usize {
-    let (graph, lists) = parse(INPUT);
+    let (rules, updates) = parse(input());
 
-    lists
-        .into_iter()
-        .filter(|list| !is_ordered(&graph, list))
-        .map(|list| sort(&graph, list))
-        .map(|list| list[list.len() / 2])
+    updates
+        .iter()
+        .map(|update| check(&rules, update))
+        .filter(|(_, correct)| !correct)
+        .map(|(middle, _)| middle)
         .sum()
 }
+
+/// Analyzes the whole rule set, see [`Analysis`].
+pub fn analyze() -> Analysis {
+    let (rules, updates) = parse(input());
+    rules.analyze(&updates)
+}
+
+#[test]
+fn test_sort() {
+    let mut rules = Rules::default();
+    for (before, after) in [(1, 2), (2, 3), (1, 3), (4, 3)] {
+        rules.add(before, after);
+    }
+
+    assert_eq!(rules.violations(&[3, 2, 1]), [(2, 3), (1, 3), (1, 2)]);
+    assert_eq!(
+        rules.sort(&[3, 2, 1]),
+        Ok(Sorted {
+            order: vec![1, 2, 3],
+            ambiguous: false
+        })
+    );
+
+    // 4 can go anywhere before 3.
+    let sorted = rules.sort(&[3, 4, 1, 2]).unwrap();
+    assert_eq!(sorted.order, [4, 1, 2, 3]);
+    assert!(sorted.ambiguous);
+
+    // A repeated page is sorted like any other, without being taken for a cycle.
+    assert_eq!(rules.violations(&[2, 1, 2]), [(1, 2)]);
+    assert_eq!(
+        rules.sort(&[2, 1, 2]),
+        Ok(Sorted {
+            order: vec![1, 2, 2],
+            ambiguous: false
+        })
+    );
+    assert_eq!(rules.sort(&[3, 4, 3]).unwrap().order, [4, 3, 3]);
+
+    rules.add(3, 1);
+    assert_eq!(rules.sort(&[1, 2, 3, 4]), Err(vec![1, 2, 3]));
+}
+
+#[test]
+fn test_analyze() {
+    let mut rules = Rules::default();
+    for (before, after) in [(1, 2), (2, 3), (1, 3), (3, 4), (1, 4)] {
+        rules.add(before, after);
+    }
+
+    let analysis = rules.analyze(&[vec![1, 5, 4], vec![6, 5]]);
+    assert!(analysis.cycles.is_empty());
+    assert!(analysis.in_cycles.is_empty());
+    assert_eq!(analysis.redundant, [(1, 3), (1, 4)]);
+    assert_eq!(analysis.reduction, [(1, 2), (2, 3), (3, 4)]);
+    assert_eq!(analysis.unconstrained, [5, 6]);
+
+    // With cycles, the reduction is between the cycles and the pages outside of them.
+    for (before, after) in [(4, 2), (7, 7), (4, 5), (1, 5)] {
+        rules.add(before, after);
+    }
+    let analysis = rules.analyze(&[]);
+    assert_eq!(analysis.cycles, [vec![2, 3, 4], vec![7]]);
+    assert_eq!(analysis.in_cycles, [(2, 3), (3, 4), (4, 2), (7, 7)]);
+    assert_eq!(analysis.reduction, [(1, 2), (4, 5)]);
+    assert_eq!(analysis.redundant, [(1, 3), (1, 4), (1, 5)]);
+
+    // Far deeper than a recursive search could go on the test thread's stack.
+    let mut chain = Rules::default();
+    let len = 200_000;
+    for page in 0..len {
+        chain.add(page, (page + 1) % len);
+    }
+    assert_eq!(chain.components(), [(0..len).collect::<Vec<_>>()]);
+}
@@ -1,6 +1,14 @@
-use std::{collections::HashSet, fmt::Display, ops};
+use std::{ops, str::FromStr};
+
+use crate::util::{
+    bitgrid::BitGrid,
+    config,
+    hash::FastSet,
+    input::{self, Block},
+    sim::Simulation,
+    span::span,
+};
 
-#[allow(dead_code)]
 static TEST: &str = include_str!("../data/d06_test");
 static INPUT: &str = include_str!("../data/d06");
 
@@ -9,12 +17,17 @@ const LEFT: Point = Point { x: -1, y: 0 };
 const DOWN: Point = Point { x: 0, y: 1 };
 const RIGHT: Point = Point { x: 1, y: 0 };
 
-#[derive(Debug, Eq, PartialEq)]
+// Clockwise, so turning right is the next direction.
+const DIRS: [Point; 4] = [UP, RIGHT, DOWN, LEFT];
+const GUARDS: [char; 4] = ['^', '>', 'v', '<'];
+
+#[derive(Debug, Clone, Eq, PartialEq)]
 enum Tile {
     Floor,
     Wall,
 }
 
+#[derive(Clone)]
 struct Map {
     tiles: Vec<Tile>,
     width: usize,
@@ -34,42 +47,14 @@ impl Map {
             None
         }
     }
-
-    fn set(&mut self, idx: &Point, val: Tile) {
-        if self.is_valid_idx(idx) {
-            let Point { x, y } = *idx;
-            self.tiles[x as usize + y as usize * self.width] = val;
-        }
-    }
-}
-
-#[derive(Clone, Copy, Hash, Eq, PartialEq)]
-struct Guard {
-    dir: Point, // directional vector
-    pos: Point, // current position
-}
-
-impl Guard {
-    fn turn(&mut self) {
-        self.dir = self.dir.rotate();
-    }
 }
 
-#[derive(Clone, Copy, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 struct Point {
     x: i32,
     y: i32,
 }
 
-impl Point {
-    fn rotate(self) -> Self {
-        Self {
-            x: -self.y,
-            y: self.x,
-        }
-    }
-}
-
 impl ops::Add for Point {
     type Output = Self;
 
@@ -86,94 +71,282 @@ impl ops::AddAssign for Point {
     }
 }
 
-struct State {
-    map: Map,
+/// What a guard does when it runs into a wall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Turn {
+    Right,
+    Left,
+    Reverse,
+}
+
+impl FromStr for Turn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "right" => Ok(Turn::Right),
+            "left" => Ok(Turn::Left),
+            "reverse" => Ok(Turn::Reverse),
+            _ => Err(format!("unknown turn `{s}`")),
+        }
+    }
+}
+
+/// The turns a guard takes at walls, one after another and starting over at the end.
+struct Rules {
+    turns: Vec<Turn>,
+}
+
+impl Rules {
+    fn from_config() -> Self {
+        let turns = config::list("d06", "turns", &[Turn::Right]);
+        assert!(!turns.is_empty(), "The guards need at least one turn");
+        Rules { turns }
+    }
+
+    /// The direction and the position in the turn sequence after the next turn.
+    fn turn(&self, dir: usize, turn: usize) -> (usize, usize) {
+        let dir = match self.turns[turn] {
+            Turn::Right => (dir + 1) % 4,
+            Turn::Left => (dir + 3) % 4,
+            Turn::Reverse => (dir + 2) % 4,
+        };
+        (dir, (turn + 1) % self.turns.len())
+    }
+
+    // Column of a guard state in a `BitGrid` which is `4 * turns` times as wide as the map.
+    fn state_index(&self, pos: Point, dir: usize, turn: usize) -> usize {
+        (pos.x as usize * 4 + dir) * self.turns.len() + turn
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+struct Guard {
+    pos: Point,
+    /// Index into `DIRS`.
+    dir: usize,
+    /// Index into the turns of the [`Rules`].
+    turn: usize,
+}
+
+/// A tile the guard walked over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Step {
+    /// The guard on the tile, facing the way it leaves.
     guard: Guard,
+    /// Whether the guard turned on this tile.
+    turned: bool,
 }
 
-impl State {
-    // This function returns the current position
-    // of the guard, not the next one.
-    //
-    // Also, if the current position is valid,
-    // it advances the guard by one step, regardless
-    // if it will end up outside of the map.
-    //
-    // If the guard is moved outside of the map, the
-    // next call to advance will return None.
-    fn advance(&mut self) -> Option<Point> {
-        let State { map, guard } = self;
-
-        if map.get(&guard.pos).is_none() {
-            None
-        } else {
-            let current_pos = guard.pos;
-            let mut next_pos = guard.pos + guard.dir;
-            let mut next_tile = map.get(&next_pos);
-
-            while let Some(Tile::Wall) = next_tile {
-                guard.turn();
-                next_pos = guard.pos + guard.dir;
-                next_tile = map.get(&next_pos);
+/// The path of a guard until it leaves the map or starts going in circles.
+struct Route {
+    steps: Vec<Step>,
+    looped: bool,
+}
+
+/// All guards walking at the same time, recording their [`Route`]s.
+struct Patrol<'a> {
+    map: &'a Map,
+    rules: &'a Rules,
+    /// `None` once the guard left the map.
+    guards: Vec<Option<Guard>>,
+    seen: FastSet<Vec<Option<Guard>>>,
+    routes: Vec<Route>,
+    turned: Vec<bool>,
+}
+
+// In every step each guard in turn either turns or moves one tile. The other guards
+// block it like walls, until they leave the map. This goes on until all guards left
+// or the guards come back to a state they were all in before.
+impl Simulation for Patrol<'_> {
+    fn step(&mut self) -> bool {
+        if !self.seen.insert(self.guards.clone()) {
+            for (guard, route) in self.guards.iter().zip(&mut self.routes) {
+                route.looped = guard.is_some();
             }
+            return false;
+        }
 
-            guard.pos = next_pos;
+        for i in 0..self.guards.len() {
+            let Some(mut guard) = self.guards[i] else {
+                continue;
+            };
+            let next = guard.pos + DIRS[guard.dir];
+            let blocked = self.guards.iter().flatten().any(|other| other.pos == next);
+
+            match self.map.get(&next) {
+                Some(Tile::Wall) => {
+                    (guard.dir, guard.turn) = self.rules.turn(guard.dir, guard.turn);
+                    self.turned[i] = true;
+                }
+                Some(Tile::Floor) if blocked => {
+                    (guard.dir, guard.turn) = self.rules.turn(guard.dir, guard.turn);
+                    self.turned[i] = true;
+                }
+                tile => {
+                    self.routes[i].steps.push(Step {
+                        guard,
+                        turned: self.turned[i],
+                    });
+                    self.turned[i] = false;
+                    guard.pos = next;
+
+                    if tile.is_none() {
+                        self.guards[i] = None;
+                        continue;
+                    }
+                }
+            }
 
-            Some(current_pos)
+            self.guards[i] = Some(guard);
         }
+
+        self.guards.iter().any(Option::is_some)
     }
 }
 
-impl Display for State {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut fmt_string = Vec::new();
+impl Route {
+    /// Walks all guards at once, returning the route of each one.
+    fn walk(map: &Map, guards: &[Guard], rules: &Rules) -> Vec<Self> {
+        let mut patrol = Patrol {
+            map,
+            rules,
+            guards: guards.iter().copied().map(Some).collect(),
+            seen: FastSet::default(),
+            routes: guards
+                .iter()
+                .map(|_| Route {
+                    steps: Vec::new(),
+                    looped: false,
+                })
+                .collect(),
+            turned: vec![false; guards.len()],
+        };
+        patrol.run_to_end();
+        patrol.routes
+    }
+}
 
-        for (i, tile) in self.map.tiles.iter().enumerate() {
-            let x = i % self.map.width;
-            let y = i / self.map.width;
+/// The map with all guards, which walk at the same time and block each other.
+struct Lab {
+    map: Map,
+    guards: Vec<Guard>,
+}
 
-            if i != 0 && x == 0 {
-                fmt_string.push('\n');
-            }
+/// For every floor tile and direction, the tile in front of the next wall, so the guard
+/// can jump from turn to turn.
+struct JumpTable {
+    width: usize,
+    // `stops[dir][y * width + x]`, `None` if the guard walks off the map.
+    stops: [Vec<Option<Point>>; 4],
+}
 
-            if self.guard.pos.x == x as i32 && self.guard.pos.y == y as i32 {
-                let c = match self.guard.dir {
-                    p if p == UP => '^',
-                    p if p == RIGHT => '>',
-                    p if p == DOWN => 'v',
-                    p if p == LEFT => '<',
-                    _ => unreachable!(),
-                };
-                fmt_string.push(c);
-            } else {
-                let c = match tile {
-                    Tile::Floor => '.',
-                    Tile::Wall => '#',
-                };
-                fmt_string.push(c);
+impl JumpTable {
+    fn new(map: &Map) -> Self {
+        let cells = map.width * map.height;
+        let stops = std::array::from_fn(|d| {
+            let dir = DIRS[d];
+            let mut stops = vec![None; cells];
+
+            // Tiles further along the direction come first, so their stop is known.
+            let forward = |n: usize| -> Vec<usize> {
+                if dir.x + dir.y > 0 {
+                    (0..n).rev().collect()
+                } else {
+                    (0..n).collect()
+                }
+            };
+
+            for y in forward(map.height) {
+                for x in forward(map.width) {
+                    let pos = Point {
+                        x: x as i32,
+                        y: y as i32,
+                    };
+                    let next = pos + dir;
+                    stops[y * map.width + x] = match map.get(&next) {
+                        None => None,
+                        Some(Tile::Wall) => Some(pos),
+                        Some(Tile::Floor) => stops[next.y as usize * map.width + next.x as usize],
+                    };
+                }
             }
+
+            stops
+        });
+
+        JumpTable {
+            width: map.width,
+            stops,
         }
-        writeln!(f, "{}", fmt_string.into_iter().collect::<String>())
     }
-}
 
-impl Iterator for State {
-    type Item = Point;
+    /// Where the guard at `pos` walking in direction `d` has to turn, with an
+    /// additional wall at `obstacle`.
+    fn jump(&self, pos: Point, d: usize, obstacle: Point) -> Option<Point> {
+        let dir = DIRS[d];
+        let stop = self.stops[d][pos.y as usize * self.width + pos.x as usize];
+
+        // How many steps it takes to reach the obstacle, if it is in front of the guard.
+        let steps = if dir.x == 0 && obstacle.x == pos.x {
+            (obstacle.y - pos.y) * dir.y
+        } else if dir.y == 0 && obstacle.y == pos.y {
+            (obstacle.x - pos.x) * dir.x
+        } else {
+            0
+        };
+        let stop_steps = stop.map(|stop| (stop.x - pos.x) * dir.x + (stop.y - pos.y) * dir.y);
+
+        if steps > 0 && stop_steps.is_none_or(|stop_steps| steps <= stop_steps) {
+            Some(Point {
+                x: obstacle.x - dir.x,
+                y: obstacle.y - dir.y,
+            })
+        } else {
+            stop
+        }
+    }
+
+    /// Whether the guard gets stuck in a loop with an additional wall at `obstacle`.
+    /// `seen` has a bit for every tile, direction and position in the turn sequence.
+    fn is_loop(&self, guard: Guard, rules: &Rules, obstacle: Point, seen: &mut BitGrid) -> bool {
+        let Guard {
+            mut pos,
+            mut dir,
+            mut turn,
+        } = guard;
+        seen.clear();
+
+        while let Some(stop) = self.jump(pos, dir, obstacle) {
+            let x = rules.state_index(stop, dir, turn);
+            if seen.get(x, stop.y as usize) {
+                return true;
+            }
+            seen.set(x, stop.y as usize);
+
+            pos = stop;
+            (dir, turn) = rules.turn(dir, turn);
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.advance()
+        false
     }
 }
 
-fn parse_input(input: &str) -> State {
-    let mut guard = Guard {
-        dir: UP,
-        pos: Point { x: 0, y: 0 },
-    };
-    let width = input.find('\n').unwrap();
-    let height = input.len() / width - 1; // subtract 1 because of the '\n' characters
+fn input() -> &'static str {
+    input::prepare(
+        "d06",
+        config::input("d06", INPUT, TEST),
+        &[Block::Grid(".#^>v<")],
+    )
+}
+
+fn parse_input(input: &str) -> Lab {
+    let _s = span("parse_input");
+    let width = input.lines().next().map_or(0, str::len);
+    let height = input.lines().count();
 
     let mut tiles = Vec::new();
+    let mut guards = Vec::new();
 
     for (y, line) in input.lines().enumerate() {
         for (x, tile) in line.chars().enumerate() {
@@ -181,74 +354,352 @@ fn parse_input(input: &str) -> State {
                 '.' => tiles.push(Tile::Floor),
                 '#' => tiles.push(Tile::Wall),
                 _ => {
-                    assert_eq!(guard.pos.x, 0);
-                    assert_eq!(guard.pos.y, 0);
-                    guard.pos = Point {
-                        x: x as i32,
-                        y: y as i32,
-                    };
+                    let dir = GUARDS
+                        .iter()
+                        .position(|guard| *guard == tile)
+                        .unwrap_or_else(|| panic!("Unknown tile {tile}"));
+                    guards.push(Guard {
+                        pos: Point {
+                            x: x as i32,
+                            y: y as i32,
+                        },
+                        dir,
+                        turn: 0,
+                    });
                     tiles.push(Tile::Floor);
                 }
             }
         }
     }
 
-    State {
+    assert!(!guards.is_empty(), "The map contains no guard");
+
+    Lab {
         map: Map {
             tiles,
             width,
             height,
         },
-        guard,
+        guards,
     }
 }
 
-fn is_cycle(state: &mut State) -> bool {
-    let mut seen = HashSet::<Guard>::new();
+fn routes(lab: &Lab, rules: &Rules) -> Vec<Route> {
+    let _s = span("walk");
+    Route::walk(&lab.map, &lab.guards, rules)
+}
 
-    while let Some(_) = state.next() {
-        let g = state.guard;
-        if seen.contains(&g) {
-            return true;
-        } else {
-            seen.insert(state.guard);
+/// Returns the tiles where a new obstacle gets any guard stuck in a loop.
+fn loop_obstacles(lab: &Lab, rules: &Rules, routes: &[Route]) -> Vec<Point> {
+    if lab.guards.len() == 1 {
+        jump_loop_obstacles(lab, rules, routes)
+    } else {
+        walk_loop_obstacles(lab, rules, routes)
+    }
+}
+
+// A single guard only has the walls in its way, so it can jump from wall to wall.
+fn jump_loop_obstacles(lab: &Lab, rules: &Rules, routes: &[Route]) -> Vec<Point> {
+    let Lab { map, guards } = lab;
+    let table = {
+        let _s = span("jump_table");
+        JumpTable::new(map)
+    };
+
+    let _s = span("obstacle_trials");
+    let mut seen = BitGrid::new(map.width * 4 * rules.turns.len(), map.height);
+    let mut tried = BitGrid::new(map.width, map.height);
+    let mut loops = BitGrid::new(map.width, map.height);
+
+    for route in routes {
+        tried.clear();
+        for guard in guards {
+            tried.set(guard.pos.x as usize, guard.pos.y as usize);
+        }
+
+        // Every obstacle is tried from the step before the guard would first walk into
+        // it, the route up to there doesn't change.
+        for Step { guard, .. } in &route.steps {
+            let next = guard.pos + DIRS[guard.dir];
+            if map.get(&next).is_none() || tried.get(next.x as usize, next.y as usize) {
+                continue;
+            }
+            tried.set(next.x as usize, next.y as usize);
+
+            if table.is_loop(*guard, rules, next, &mut seen) {
+                loops.set(next.x as usize, next.y as usize);
+            }
+        }
+    }
+
+    loops
+        .iter_ones()
+        .map(|(x, y)| Point {
+            x: x as i32,
+            y: y as i32,
+        })
+        .collect()
+}
+
+// Other guards move out of the way, so every obstacle is tried by walking all guards
+// again. Only tiles some guard walks onto can change anything.
+fn walk_loop_obstacles(lab: &Lab, rules: &Rules, routes: &[Route]) -> Vec<Point> {
+    let _s = span("obstacle_walks");
+    let mut tried = BitGrid::new(lab.map.width, lab.map.height);
+    for guard in &lab.guards {
+        tried.set(guard.pos.x as usize, guard.pos.y as usize);
+    }
+
+    let mut loops = Vec::new();
+    for Step { guard, .. } in routes.iter().flat_map(|route| &route.steps) {
+        let next = guard.pos + DIRS[guard.dir];
+        if lab.map.get(&next).is_none() || tried.get(next.x as usize, next.y as usize) {
+            continue;
+        }
+        tried.set(next.x as usize, next.y as usize);
+
+        let mut map = lab.map.clone();
+        map.tiles[next.y as usize * map.width + next.x as usize] = Tile::Wall;
+        if Route::walk(&map, &lab.guards, rules)
+            .iter()
+            .any(|route| route.looped)
+        {
+            loops.push(next);
         }
     }
 
-    false
+    loops
+}
+
+/// Draws the routes like the puzzle does: `|` and `-` for the way the guards walked,
+/// `+` where they turned or crossed and `O` for obstacles which cause a loop.
+fn draw(lab: &Lab, routes: &[Route], obstacles: &[Point]) -> String {
+    let Map { width, height, .. } = lab.map;
+    let mut grid: Vec<Vec<char>> = lab
+        .map
+        .tiles
+        .chunks(width)
+        .map(|row| {
+            row.iter()
+                .map(|tile| match tile {
+                    Tile::Floor => '.',
+                    Tile::Wall => '#',
+                })
+                .collect()
+        })
+        .collect();
+
+    for Step { guard, turned } in routes.iter().flat_map(|route| &route.steps) {
+        let cell = &mut grid[guard.pos.y as usize][guard.pos.x as usize];
+        let line = if guard.dir % 2 == 0 { '|' } else { '-' };
+        *cell = match *cell {
+            _ if *turned => '+',
+            '.' => line,
+            c if c == line => line,
+            _ => '+',
+        };
+    }
+
+    for guard in &lab.guards {
+        grid[guard.pos.y as usize][guard.pos.x as usize] = GUARDS[guard.dir];
+    }
+    for obstacle in obstacles {
+        grid[obstacle.y as usize][obstacle.x as usize] = 'O';
+    }
+
+    let mut drawing = String::with_capacity((width + 1) * height);
+    for row in grid {
+        drawing.extend(row);
+        drawing.push('\n');
+    }
+    drawing
+}
+
+/// The map with the routes of all guards and the obstacles which make them loop.
+pub fn to_route_map() -> String {
+    let lab = parse_input(input());
+    let rules = Rules::from_config();
+    let routes = routes(&lab, &rules);
+    let obstacles = loop_obstacles(&lab, &rules, &routes);
+    let mut drawing = draw(&lab, &routes, &obstacles);
+
+    // The turn points of every guard, in the order they are reached.
+    for (guard, route) in lab.guards.iter().zip(&routes) {
+        let turns: Vec<String> = route
+            .steps
+            .iter()
+            .filter(|step| step.turned)
+            .map(|Step { guard, .. }| format!("{},{}", guard.pos.x, guard.pos.y))
+            .collect();
+        drawing += &format!(
+            "guard at {},{}: {} steps, {}, turns at {}\n",
+            guard.pos.x,
+            guard.pos.y,
+            route.steps.len(),
+            if route.looped {
+                "loops"
+            } else {
+                "leaves the map"
+            },
+            turns.join(" ")
+        );
+    }
+
+    drawing
 }
 
 pub fn solve_1() -> usize {
-    parse_input(INPUT).collect::<HashSet<Point>>().len()
+    let lab = parse_input(input());
+    let mut visited = BitGrid::new(lab.map.width, lab.map.height);
+
+    // A guard which is boxed in never moves, but it still stands on its tile.
+    for guard in &lab.guards {
+        visited.set(guard.pos.x as usize, guard.pos.y as usize);
+    }
+    for route in routes(&lab, &Rules::from_config()) {
+        for step in route.steps {
+            visited.set(step.guard.pos.x as usize, step.guard.pos.y as usize);
+        }
+    }
+
+    visited.count_ones()
 }
 
 pub fn solve_2() -> usize {
-    let mut state = parse_input(INPUT);
-    let start = state.guard;
-    // I have to collect these into a Vec.
-    // If using a Hashset it sometimes leads to
-    // an off by one error (probably due to the order being unstable)
-    // and i haven't figured out why yet.
-    let mut visited = Vec::new();
-    (&mut state).for_each(|p| {
-        if !visited.contains(&p) {
-            visited.push(p);
+    let lab = parse_input(input());
+    let rules = Rules::from_config();
+    let routes = routes(&lab, &rules);
+    loop_obstacles(&lab, &rules, &routes).len()
+}
+
+#[test]
+fn test_routes() {
+    let lab = parse_input("....#.....\n.........#\n..........\n..#.......\n.......#..\n..........\n.#..^.....\n........#.\n#.........\n......#...\n");
+    let rules = Rules {
+        turns: vec![Turn::Right],
+    };
+    let routes = routes(&lab, &rules);
+    assert!(!routes[0].looped);
+    assert_eq!(
+        routes[0].steps.iter().filter(|step| step.turned).count(),
+        10
+    );
+
+    let obstacles = loop_obstacles(&lab, &rules, &routes);
+    assert_eq!(obstacles.len(), 6);
+
+    let drawing = draw(&lab, &routes, &obstacles);
+    assert_eq!(drawing.lines().nth(6), Some(".#+O^-+-+."));
+
+    // Turning around at every wall walks back and forth between two walls.
+    let rules = Rules {
+        turns: vec![Turn::Reverse],
+    };
+    let lab = parse_input("#..>.#\n");
+    assert!(Route::walk(&lab.map, &lab.guards, &rules)[0].looped);
+
+    // The guards walk at the same time and turn around in front of each other.
+    let xs =
+        |route: &Route| -> Vec<i32> { route.steps.iter().map(|step| step.guard.pos.x).collect() };
+    let lab = parse_input(".>.<.\n");
+    let routes = Route::walk(&lab.map, &lab.guards, &rules);
+    assert_eq!(xs(&routes[0]), [1, 2, 1, 0]);
+    assert_eq!(xs(&routes[1]), [3, 4]);
+    assert!(routes.iter().all(|route| !route.looped));
+
+    // Facing each other between two walls, neither guard ever moves.
+    let lab = parse_input("#><#\n");
+    let routes = Route::walk(&lab.map, &lab.guards, &rules);
+    assert!(routes
+        .iter()
+        .all(|route| route.looped && route.steps.is_empty()));
+}
+
+#[test]
+fn test_loop_obstacles() {
+    use crate::util::math::random_numbers;
+
+    // Puts each obstacle into the map and walks the guards tile by tile.
+    fn simulated(grid: &str, rules: &Rules) -> Vec<Point> {
+        let width = grid.find('\n').unwrap() + 1;
+        let mut obstacles = Vec::new();
+
+        for (i, _) in grid.char_indices().filter(|(_, tile)| *tile == '.') {
+            let mut blocked = grid.to_string();
+            blocked.replace_range(i..=i, "#");
+            let lab = parse_input(&blocked);
+
+            if Route::walk(&lab.map, &lab.guards, rules)
+                .iter()
+                .any(|route| route.looped)
+            {
+                obstacles.push(Point {
+                    x: (i % width) as i32,
+                    y: (i / width) as i32,
+                });
+            }
         }
-    });
-    let mut count = 0;
 
-    // Brute force approach
-    for point in visited {
-        state.map.set(&point, Tile::Wall);
+        obstacles
+    }
 
-        if is_cycle(&mut state) {
-            count += 1;
+    // Returns `None` if a guard is stuck without any new obstacle, where only the
+    // tiles on its route are tried.
+    fn compare(grid: &str, rules: &Rules) -> Option<usize> {
+        let lab = parse_input(grid);
+        let routes = routes(&lab, rules);
+        if routes.iter().any(|route| route.looped) {
+            return None;
         }
 
-        // Reset the map
-        state.map.set(&point, Tile::Floor);
-        state.guard = start;
+        let by_row = |p: &Point| (p.y, p.x);
+        let mut obstacles = loop_obstacles(&lab, rules, &routes);
+        obstacles.sort_by_key(by_row);
+        let mut expected = simulated(grid, rules);
+        expected.sort_by_key(by_row);
+
+        assert_eq!(obstacles, expected, "\n{grid}");
+        Some(obstacles.len())
+    }
+
+    let right = Rules {
+        turns: vec![Turn::Right],
+    };
+    let example = "....#.....\n.........#\n..........\n..#.......\n.......#..\n..........\n.#..^.....\n........#.\n#.........\n......#...\n";
+    assert_eq!(compare(example, &right), Some(6));
+
+    let mixed = Rules {
+        turns: vec![Turn::Left, Turn::Reverse, Turn::Right],
+    };
+    let (width, height) = (16, 12);
+    let mut numbers = random_numbers(48);
+    let (mut compared, mut found) = (0, 0);
+
+    // A single guard uses the jump table, several guards are walked with every obstacle.
+    for round in 0..40 {
+        let mut tiles: Vec<char> = numbers
+            .by_ref()
+            .take(width * height)
+            .map(|n| if n % 4 == 0 { '#' } else { '.' })
+            .collect();
+        for _ in 0..1 + round % 2 {
+            let cell = numbers.next().unwrap() as usize % tiles.len();
+            tiles[cell] = GUARDS[numbers.next().unwrap() as usize % 4];
+        }
+
+        let grid: String = tiles
+            .chunks(width)
+            .map(|row| row.iter().collect::<String>() + "\n")
+            .collect();
+        for rules in [&right, &mixed] {
+            if let Some(obstacles) = compare(&grid, rules) {
+                compared += 1;
+                found += obstacles;
+            }
+        }
     }
 
-    count
+    // Most of the random grids have to be usable for the comparison to mean anything.
+    assert!(compared > 40, "only {compared} grids compared");
+    assert!(found > compared, "only {found} obstacles found");
 }
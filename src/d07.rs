@@ -1,4 +1,10 @@
-#[allow(dead_code)]
+use crate::util::{
+    checked, config,
+    input::{self, Block},
+    math, parse,
+    trace::{self, Event, Value},
+};
+
 static TEST: &str = include_str!("../data/d07_test");
 static INPUT: &str = include_str!("../data/d07");
 
@@ -7,66 +13,295 @@ struct Equation {
     nums: Vec<u64>,
 }
 
-fn parse_input(input: &str) -> Vec<Equation> {
-    let mut equations = Vec::new();
-
-    for line in input.lines() {
-        let colon = line.find(':').expect("No colon found in equation");
-        let result = line[..colon]
-            .parse()
-            .expect("Invalid number for result of equation.");
-        let nums = line[colon + 2..]
-            .split(" ")
-            .map(|n| n.parse())
-            .collect::<Result<Vec<u64>, _>>()
-            .expect("Invalid number for nums of equation");
-
-        equations.push(Equation { result, nums });
+impl Equation {
+    /// Writes the equation with `operators` between the numbers, e.g. `190 = 10 * 19`.
+    fn render(&self, operators: &[&str]) -> String {
+        let mut equation = format!("{} =", self.result);
+        for (i, num) in self.nums.iter().enumerate() {
+            if i > 0 {
+                equation.push_str(&format!(" {}", operators[i - 1]));
+            }
+            equation.push_str(&format!(" {num}"));
+        }
+        equation
     }
+}
+
+/// The left operands which give a result with a given right operand.
+enum Inverse {
+    None,
+    One(u64),
+    /// Every left operand does, e.g. when multiplying by zero.
+    Any,
+}
+
+/// A binary operator. Equations are evaluated from left to right.
+#[derive(Clone, Copy)]
+struct Operator {
+    symbol: &'static str,
+    /// `None` if the result doesn't fit into a `u64`.
+    apply: fn(u64, u64) -> Option<u64>,
+    /// Has to agree with `apply`, the solver relies on it to go backwards.
+    undo: fn(u64, u64) -> Inverse,
+}
+
+const OPERATORS: [Operator; 5] = [
+    Operator {
+        symbol: "+",
+        apply: u64::checked_add,
+        undo: |result, right| {
+            result
+                .checked_sub(right)
+                .map_or(Inverse::None, Inverse::One)
+        },
+    },
+    Operator {
+        symbol: "*",
+        apply: u64::checked_mul,
+        undo: |result, right| match (result, right) {
+            (0, 0) => Inverse::Any,
+            (_, 0) => Inverse::None,
+            _ if result % right == 0 => Inverse::One(result / right),
+            _ => Inverse::None,
+        },
+    },
+    Operator {
+        symbol: "||",
+        apply: math::concat,
+        undo: |result, right| math::split_concat(result, right).map_or(Inverse::None, Inverse::One),
+    },
+    Operator {
+        symbol: "-",
+        apply: u64::checked_sub,
+        undo: |result, right| {
+            result
+                .checked_add(right)
+                .map_or(Inverse::None, Inverse::One)
+        },
+    },
+    Operator {
+        symbol: "^",
+        apply: |left, right| left.checked_pow(u32::try_from(right).ok()?),
+        undo: |result, right| match (result, u32::try_from(right)) {
+            (1, Ok(0)) => Inverse::Any,
+            (_, Ok(k)) => math::exact_root(result, k).map_or(Inverse::None, Inverse::One),
+            (_, Err(_)) => Inverse::None,
+        },
+    },
+];
+
+fn operators(symbols: &[String]) -> Vec<Operator> {
+    symbols
+        .iter()
+        .map(|symbol| {
+            *OPERATORS
+                .iter()
+                .find(|operator| operator.symbol == symbol)
+                .unwrap_or_else(|| panic!("Unknown operator {symbol}"))
+        })
+        .collect()
+}
+
+fn input() -> &'static str {
+    input::prepare(
+        "d07",
+        config::input("d07", INPUT, TEST),
+        &[Block::Lines("0-9: ")],
+    )
+}
+
+fn parse_input(input: &str) -> Vec<Equation> {
+    parse::lines(input, |line| {
+        parse::key_values(line).map(|(result, nums)| Equation { result, nums })
+    })
+    .unwrap_or_else(|e| panic!("Invalid equation at {e}"))
+}
 
-    equations
+struct Calibration {
+    result: u64,
+    equation: Option<String>,
 }
 
-fn is_true(Equation { result, nums }: &Equation, do_concat: bool) -> bool {
-    if nums.is_empty() {
-        false
-    } else {
-        is_true_inner(&nums[1..], nums[0], *result, do_concat)
+impl Event for Calibration {
+    const KIND: &'static str = "calibration";
+
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("result", self.result.into()),
+            ("equation", self.equation.clone().into()),
+        ]
     }
 }
 
-fn is_true_inner(nums: &[u64], sum: u64, result: u64, do_concat: bool) -> bool {
-    if nums.is_empty() {
-        sum == result
-    } else {
-        is_true_inner(&nums[1..], sum * nums[0], result, do_concat)
-            || is_true_inner(&nums[1..], sum + nums[0], result, do_concat)
-            || (do_concat && is_true_inner(&nums[1..], concat(sum, nums[0]), result, do_concat))
+/// Returns up to `limit` sequences of operators which make the equation true.
+///
+/// The search starts at the result and undoes the last operation, so every operator
+/// only leads to a single left operand, if any. Most branches die right away, e.g.
+/// because the result isn't divisible by the last number.
+fn solutions(equation: &Equation, operators: &[Operator], limit: usize) -> Vec<Vec<&'static str>> {
+    let mut found = Vec::new();
+    if !equation.nums.is_empty() {
+        let mut suffix = Vec::new();
+        backward(
+            &equation.nums,
+            equation.result,
+            operators,
+            &mut suffix,
+            &mut found,
+            limit,
+        );
     }
+    found
 }
 
-fn concat(a: u64, b: u64) -> u64 {
-    let mut zero_padding = 1;
+// `suffix` holds the operators after `nums`, the last one first.
+fn backward(
+    nums: &[u64],
+    target: u64,
+    operators: &[Operator],
+    suffix: &mut Vec<&'static str>,
+    found: &mut Vec<Vec<&'static str>>,
+    limit: usize,
+) {
+    let Some((&last, rest)) = nums.split_last() else {
+        return;
+    };
 
-    while b / zero_padding != 0 {
-        zero_padding *= 10;
+    if rest.is_empty() {
+        if last == target && found.len() < limit {
+            found.push(suffix.iter().rev().copied().collect());
+        }
+        return;
     }
 
-    a * zero_padding + b
+    for operator in operators {
+        if found.len() >= limit {
+            return;
+        }
+
+        suffix.push(operator.symbol);
+        match (operator.undo)(target, last) {
+            Inverse::None => (),
+            Inverse::One(left) => backward(rest, left, operators, suffix, found, limit),
+            Inverse::Any => {
+                let mut prefix = Vec::new();
+                forward(
+                    &rest[1..],
+                    rest[0],
+                    operators,
+                    &mut prefix,
+                    suffix,
+                    found,
+                    limit,
+                );
+            }
+        }
+        suffix.pop();
+    }
 }
 
-pub fn solve_1() -> u64 {
-    parse_input(INPUT)
+// Finds the operators for `nums` which can be evaluated at all, for when the operators
+// after them make any value true.
+fn forward(
+    nums: &[u64],
+    value: u64,
+    operators: &[Operator],
+    prefix: &mut Vec<&'static str>,
+    suffix: &[&'static str],
+    found: &mut Vec<Vec<&'static str>>,
+    limit: usize,
+) {
+    let Some((&first, rest)) = nums.split_first() else {
+        if found.len() < limit {
+            found.push(prefix.iter().chain(suffix.iter().rev()).copied().collect());
+        }
+        return;
+    };
+
+    for operator in operators {
+        if found.len() >= limit {
+            return;
+        }
+
+        if let Some(value) = (operator.apply)(value, first) {
+            prefix.push(operator.symbol);
+            forward(rest, value, operators, prefix, suffix, found, limit);
+            prefix.pop();
+        }
+    }
+}
+
+fn is_true(equation: &Equation, operators: &[Operator]) -> bool {
+    let solution = solutions(equation, operators, 1).pop();
+    let is_true = solution.is_some();
+
+    trace::emit(|| Calibration {
+        result: equation.result,
+        equation: solution.map(|operators| equation.render(&operators)),
+    });
+
+    is_true
+}
+
+fn calibrate(key: &str, default: &[&str]) -> u64 {
+    let default: Vec<String> = default.iter().map(|symbol| symbol.to_string()).collect();
+    let operators = operators(&config::list("d07", key, &default));
+
+    parse_input(input())
         .into_iter()
-        .filter(|eq| is_true(eq, false))
+        .filter(|eq| is_true(eq, &operators))
         .map(|Equation { result, .. }| result)
-        .sum()
+        .fold(0, checked::add)
+}
+
+pub fn solve_1() -> u64 {
+    calibrate("operators_1", &["*", "+"])
 }
 
 pub fn solve_2() -> u64 {
-    parse_input(INPUT)
-        .into_iter()
-        .filter(|eq| is_true(eq, true))
-        .map(|Equation { result, .. }| result)
-        .sum()
+    calibrate("operators_2", &["*", "+", "||"])
+}
+
+#[test]
+fn test_solutions() {
+    let all = operators(&["+", "*", "||", "-", "^"].map(String::from));
+
+    let equation = parse_input("7290: 6 8 6 15\n").pop().unwrap();
+    let found = solutions(&equation, &all[..3], usize::MAX);
+    assert_eq!(found, [["*", "||", "*"]]);
+    assert_eq!(equation.render(&found[0]), "7290 = 6 * 8 || 6 * 15");
+    assert!(solutions(&equation, &all[..2], usize::MAX).is_empty());
+
+    // Above 2^53 the root can't be taken with floats alone.
+    let big = (1u64 << 60) + 3;
+    let equation = parse_input(&format!("{big}: {big} 1\n")).pop().unwrap();
+    assert_eq!(solutions(&equation, &all, usize::MAX), [["*"], ["^"]]);
+
+    // Compare with trying every sequence of operators, including ones which can't be undone.
+    let mut numbers = math::random_numbers(7).map(|n| n % 4);
+    for _ in 0..500 {
+        let nums: Vec<u64> = numbers.by_ref().take(4).collect();
+        let result = numbers.next().unwrap() * 3;
+        let equation = Equation { result, nums };
+
+        let mut expected = Vec::new();
+        for mut i in 0..all.len().pow(3) {
+            let mut value = Some(equation.nums[0]);
+            let mut sequence = Vec::new();
+            for num in &equation.nums[1..] {
+                let operator = all[i % all.len()];
+                i /= all.len();
+                value = value.and_then(|value| (operator.apply)(value, *num));
+                sequence.push(operator.symbol);
+            }
+            if value == Some(result) {
+                expected.push(sequence);
+            }
+        }
+
+        let mut found = solutions(&equation, &all, usize::MAX);
+        found.sort();
+        expected.sort();
+        assert_eq!(found, expected, "{}", equation.render(&["?"; 3]));
+    }
 }
@@ -1,6 +1,9 @@
-use std::collections::{HashMap, HashSet};
+use crate::util::{
+    config,
+    hash::{FastMap, FastSet},
+    input::{self, Block},
+};
 
-#[allow(dead_code)]
 static TEST: &str = include_str!("../data/d08_test");
 static INPUT: &str = include_str!("../data/d08");
 
@@ -51,17 +54,21 @@ impl Point {
     }
 }
 
-type Antennas = HashMap<char, Vec<Point>>;
+type Antennas = FastMap<char, Vec<Point>>;
 
-fn parse_input(input: &str) -> (i32, i32, Antennas) {
-    assert_eq!(input[input.len() - 1..], *"\n");
-    let width = input
-        .find('\n')
-        .expect("input contains no newline characters");
+fn input() -> &'static str {
+    input::prepare(
+        "d08",
+        config::input("d08", INPUT, TEST),
+        &[Block::Grid(".0-9a-zA-Z")],
+    )
+}
 
-    let height = input.len() / (width + 1);
+fn parse_input(input: &str) -> (i32, i32, Antennas) {
+    let width = input.lines().next().map_or(0, str::len);
+    let height = input.lines().count();
 
-    let mut antennas = HashMap::new();
+    let mut antennas = FastMap::default();
 
     for (y, line) in input.lines().enumerate() {
         for (x, id) in line.chars().enumerate() {
@@ -103,16 +110,16 @@ fn get_number_of_unique_antinodes(
 ) -> usize {
     get_antinodes(antennas, width, height, limit)
         .into_iter()
-        .collect::<HashSet<Point>>()
+        .collect::<FastSet<Point>>()
         .len()
 }
 
 pub fn solve_1() -> usize {
-    let (width, height, antennas) = parse_input(INPUT);
+    let (width, height, antennas) = parse_input(input());
     get_number_of_unique_antinodes(antennas, width, height, 2)
 }
 
 pub fn solve_2() -> usize {
-    let (width, height, antennas) = parse_input(INPUT);
+    let (width, height, antennas) = parse_input(input());
     get_number_of_unique_antinodes(antennas, width, height, width.max(height) + 1)
 }
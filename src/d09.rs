@@ -1,4 +1,5 @@
-#[allow(dead_code)]
+use crate::util::{checked, config, input};
+
 static TEST: &str = include_str!("../data/d09_test");
 static INPUT: &str = include_str!("../data/d09");
 
@@ -9,10 +10,18 @@ struct Block {
     id: usize,
 }
 
+fn input() -> &'static str {
+    input::prepare(
+        "d09",
+        config::input("d09", INPUT, TEST),
+        &[input::Block::Lines("0-9")],
+    )
+}
+
 fn parse_input(input: &str) -> Vec<Option<u16>> {
     let mut fs = Vec::new();
 
-    for (id, c) in input[0..input.len() - 1].chars().enumerate() {
+    for (id, c) in input.trim_end().chars().enumerate() {
         let size = c.to_digit(10).expect("Input has non numerical characters");
         let n = if id % 2 == 0 {
             Some(id as u16 / 2)
@@ -32,7 +41,7 @@ fn parse_input_2(input: &str) -> Vec<Block> {
     let mut start = 0;
     let mut blocks = Vec::new();
 
-    for (id, c) in input[0..input.len() - 1].chars().enumerate() {
+    for (id, c) in input.trim_end().chars().enumerate() {
         if let Some(len) = c.to_digit(10) {
             if id % 2 == 0 {
                 blocks.push(Block {
@@ -102,24 +111,52 @@ fn checksum(fs: Vec<Option<u16>>) -> usize {
 }
 
 fn checksum_2(fs: Vec<Block>) -> usize {
-    fs.into_iter().map(sum).sum()
+    checked::sum(fs.into_iter().map(sum))
 }
 
+// Sum of the positions the block covers times its id
 fn sum(block: Block) -> usize {
-    let a = block.start;
-    let b = block.start + block.len - 1;
+    if block.len == 0 {
+        return 0;
+    }
 
-    ((b * (b + 1) - a * (a - 1)) / 2) * block.id
+    let first = block.start;
+    let last = checked::sub(checked::add(block.start, block.len), 1);
+
+    checked::mul(
+        checked::mul(checked::add(first, last), block.len) / 2,
+        block.id,
+    )
 }
 
 pub fn solve_1() -> usize {
-    let mut fs = parse_input(INPUT);
+    let mut fs = parse_input(input());
     compact(&mut fs);
     checksum(fs)
 }
 
 pub fn solve_2() -> usize {
-    let mut fs = parse_input_2(INPUT);
+    let mut fs = parse_input_2(input());
     compact_2(&mut fs);
     checksum_2(fs)
 }
+
+#[test]
+fn test_sum() {
+    assert_eq!(
+        sum(Block {
+            start: 2,
+            len: 3,
+            id: 5
+        }),
+        (2 + 3 + 4) * 5
+    );
+    assert_eq!(
+        sum(Block {
+            start: 0,
+            len: 0,
+            id: 7
+        }),
+        0
+    );
+}
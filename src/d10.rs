@@ -1,6 +1,9 @@
-use std::collections::HashSet;
+use crate::util::{
+    config,
+    input::{self, Block},
+    search::{self, SearchProblem, Stats},
+};
 
-#[allow(dead_code)]
 static TEST: &str = include_str!("../data/d10_test");
 static INPUT: &str = include_str!("../data/d10");
 
@@ -24,6 +27,14 @@ impl Map {
     }
 }
 
+fn input() -> &'static str {
+    input::prepare(
+        "d10",
+        config::input("d10", INPUT, TEST),
+        &[Block::Grid("0-9.")],
+    )
+}
+
 fn parse_input(input: &str) -> Map {
     let lines: Vec<&str> = input.lines().collect();
     let height = lines.len();
@@ -47,34 +58,20 @@ fn is_one_higher(current_height: u8, next_height: u8) -> bool {
     next_height.wrapping_sub(current_height) == 1
 }
 
-fn check_and_push_on_stack(
-    map: &Map,
-    x: usize,
-    y: usize,
-    current_height: u8,
-    stack: &mut Vec<(usize, usize, u8)>,
-) {
-    if let Some(next_height) = map
-        .get(x, y)
-        .filter(|next_height| is_one_higher(current_height, *next_height))
-    {
-        stack.push((x, y, next_height))
-    }
+struct Trail<'a> {
+    map: &'a Map,
+    trailhead: (usize, usize),
 }
 
-fn count_trails(map: &Map, x: usize, y: usize, is_part_1: bool) -> usize {
-    let mut stack = vec![(x, y, 0)];
-    let mut seen: HashSet<(usize, usize)> = HashSet::new();
-    let mut sum = 0;
+impl SearchProblem for Trail<'_> {
+    type State = (usize, usize);
 
-    while let Some((x, y, current_height)) = stack.pop() {
-        if current_height == 9 && !seen.contains(&(x, y)) {
-            sum += 1;
-            if is_part_1 {
-                seen.insert((x, y));
-            }
-            continue;
-        }
+    fn start(&self) -> Vec<(usize, usize)> {
+        vec![self.trailhead]
+    }
+
+    fn successors(&self, &(x, y): &(usize, usize), next: &mut Vec<((usize, usize), usize)>) {
+        let current_height = self.map.get(x, y).unwrap();
 
         for (x1, y1) in [
             (x.wrapping_sub(1), y),
@@ -82,25 +79,49 @@ fn count_trails(map: &Map, x: usize, y: usize, is_part_1: bool) -> usize {
             (x + 1, y),
             (x, y + 1),
         ] {
-            check_and_push_on_stack(map, x1, y1, current_height, &mut stack);
+            if self
+                .map
+                .get(x1, y1)
+                .is_some_and(|next_height| is_one_higher(current_height, next_height))
+            {
+                next.push(((x1, y1), 1));
+            }
         }
     }
 
-    sum
+    fn is_goal(&self, &(x, y): &(usize, usize)) -> bool {
+        self.map.get(x, y) == Some(9)
+    }
 }
 
 fn solve(is_part_1: bool) -> usize {
-    let map = parse_input(INPUT);
+    let map = parse_input(input());
     let mut sum = 0;
+    let mut stats = Stats::default();
+
     for y in 0..map.height {
         for x in 0..map.width {
-            if map.is_trailhead(x, y) {
-                let result = count_trails(&map, x, y, is_part_1);
-                sum += result;
+            if !map.is_trailhead(x, y) {
+                continue;
+            }
+
+            let trail = Trail {
+                map: &map,
+                trailhead: (x, y),
+            };
+            if is_part_1 {
+                let (peaks, trail_stats) = search::reachable_goals(&trail);
+                sum += peaks.len();
+                stats += trail_stats;
+            } else {
+                sum += search::count_paths(&trail);
             }
         }
     }
 
+    if is_part_1 {
+        stats.report("reachable_peaks");
+    }
     sum
 }
 
@@ -1,10 +1,17 @@
-use std::{collections::HashMap, fmt::Display};
+use std::fmt::Display;
+
+use crate::util::{
+    checked, config,
+    input::{self, Block},
+    math,
+    memo::memoize,
+    sim::{Periodic, Simulation},
+};
 
-#[allow(dead_code)]
 static TEST: &str = include_str!("../data/d11_test");
 static INPUT: &str = include_str!("../data/d11");
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct SplitNode<T> {
     value: T,
     next: usize,
@@ -16,7 +23,7 @@ impl<T> SplitNode<T> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct SplitVec<T> {
     inner: Vec<SplitNode<T>>,
     back: usize,
@@ -63,9 +70,19 @@ impl<T> SplitVec<T> {
     fn get(&self, index: usize) -> Option<&SplitNode<T>> {
         self.inner.get(index)
     }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        let mut current = (!self.inner.is_empty()).then_some(0);
+
+        std::iter::from_fn(move || {
+            let node = self.get(current?)?;
+            current = (node.next != 0).then_some(node.next);
+            Some(&node.value)
+        })
+    }
 }
 
-impl Display for SplitVec<usize> {
+impl Display for SplitVec<u64> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut buffer = String::new();
         let mut current = 0;
@@ -84,7 +101,7 @@ impl Display for SplitVec<usize> {
     }
 }
 
-fn loop_over(line: &mut SplitVec<usize>) {
+fn loop_over(line: &mut SplitVec<u64>) {
     if line.len() == 0 {
         return;
     }
@@ -95,9 +112,12 @@ fn loop_over(line: &mut SplitVec<usize>) {
         if value == 0 {
             line.inner.get_mut(current).unwrap().value = 1;
         } else {
-            match split_number(value) {
-                Some(ten) => next = line.split(current, value / ten, value % ten),
-                None => line.inner.get_mut(current).unwrap().value *= 2024,
+            match math::split_digits(value) {
+                Some((left, right)) => next = line.split(current, left, right),
+                None => {
+                    let node = line.inner.get_mut(current).unwrap();
+                    node.value = checked::mul(node.value, 2024);
+                }
             }
         }
 
@@ -109,107 +129,88 @@ fn loop_over(line: &mut SplitVec<usize>) {
     }
 }
 
-fn split_number(mut n: usize) -> Option<usize> {
-    // Check how many digits a number has and return 10^(number_of_digits / 2) if it is an even number
-    let mut m = 0;
-    let mut ten = 1;
+// Every step is one blink.
+impl Simulation for SplitVec<u64> {
+    fn step(&mut self) -> bool {
+        loop_over(self);
+        true
+    }
+}
+
+impl Periodic for SplitVec<u64> {
+    type Fingerprint = Vec<u64>;
+    type Snapshot = Self;
 
-    while n > 0 {
-        n /= 10;
-        m += 1;
-        if m % 2 == 0 {
-            ten *= 10;
-        }
+    fn fingerprint(&self) -> Vec<u64> {
+        self.iter().copied().collect()
     }
 
-    if m % 2 == 0 {
-        Some(ten)
-    } else {
-        None
+    fn snapshot(&self) -> Self {
+        self.clone()
     }
+
+    fn restore(&mut self, snapshot: &Self) {
+        self.clone_from(snapshot);
+    }
+}
+
+fn input() -> &'static str {
+    input::prepare(
+        "d11",
+        config::input("d11", INPUT, TEST),
+        &[Block::Lines("0-9 ")],
+    )
 }
 
-fn parse_input(input: &str) -> SplitVec<usize> {
+fn parse_input(input: &str) -> SplitVec<u64> {
     let mut list = SplitVec::new();
 
     input
         .split_whitespace()
-        .filter_map(|n| n.parse::<usize>().ok())
+        .filter_map(|n| n.parse::<u64>().ok())
         .for_each(|n| list.push(n));
 
     list
 }
 
 pub fn solve_1() -> usize {
-    let mut list = parse_input(INPUT);
-    for _ in 0..25 {
-        loop_over(&mut list);
-    }
+    let mut list = parse_input(input());
+    list.run_n(config::param("d11", "blinks_1", 25));
     list.len()
 }
 
-pub fn solve_2() -> usize {
-    // determine, for each number, the resulting numbers at each step, before they become single digits again
-    // store data like so:
-    // store one map: number and an id
-    // store one vec which for each id contains the current count. and an additional vec for
-    // on each iteration, create a new vec with the new counts.
-    // at the end, sum up all counts
-    let mut id_to_number: Vec<usize> = INPUT
-        .split_whitespace()
-        .filter_map(|n| n.parse::<usize>().ok())
-        .collect();
-    let mut number_to_id: HashMap<usize, usize> = id_to_number
-        .iter()
-        .enumerate()
-        .map(|(id, number)| (*number, id))
-        .collect();
-    let mut id_to_count = vec![1; id_to_number.len()];
-    let mut id_count = id_to_number.len();
-
-    // Update the maps on an iteration:
-    for _ in 0..75 {
-        let mut updated_id_to_count = id_to_count.clone();
-        assert_eq!(id_to_count.len(), id_to_number.len());
-        let max_id = id_count;
-
-        for id in 0..max_id {
-            let count = id_to_count[id];
-            let number = id_to_number[id];
-            // Calculate how the number transforms in the next loop
-            let next_numbers = if number == 0 {
-                [Some(1), None]
-            } else {
-                match split_number(number) {
-                    None => [Some(number * 2024), None],
-                    Some(split) => [Some(number / split), Some(number % split)],
-                }
-            };
-
-            for next_number in next_numbers.iter().filter_map(|n| *n) {
-                // Get the id of the next number
-                let next_id = match number_to_id.get(&next_number) {
-                    Some(next_id) => *next_id,
-                    None => {
-                        // If the number was not known before, insert it
-                        number_to_id.insert(next_number, id_count);
-                        id_count += 1;
-                        updated_id_to_count.push(0);
-                        id_to_number.push(next_number);
-                        id_count - 1
-                    }
-                };
-
-                updated_id_to_count[next_id] += count;
+// The stones don't influence each other, so the number of stones a single stone
+// turns into after some blinks can be cached and reused for every stone with the same number.
+fn count_stones(
+    blink: &mut dyn FnMut((u64, usize)) -> usize,
+    (stone, blinks): (u64, usize),
+) -> usize {
+    if blinks == 0 {
+        1
+    } else if stone == 0 {
+        blink((1, blinks - 1))
+    } else {
+        match math::split_digits(stone) {
+            Some((left, right)) => {
+                checked::add(blink((left, blinks - 1)), blink((right, blinks - 1)))
             }
-
-            // An iteration "removes" the number from the list.
-            updated_id_to_count[id] -= count;
+            None => blink((checked::mul(stone, 2024), blinks - 1)),
         }
-
-        // Swap out the old counts with the new one
-        id_to_count = updated_id_to_count;
     }
+}
+
+pub fn solve_2() -> usize {
+    let mut count = memoize(count_stones);
+
+    let blinks = config::param("d11", "blinks_2", 75);
+
+    let stones = checked::sum(
+        input()
+            .split_whitespace()
+            .filter_map(|n| n.parse::<u64>().ok())
+            .map(|stone| count.call((stone, blinks))),
+    );
 
-    id_to_count.into_iter().sum()
+    count.report("count_stones");
+    stones
 }
@@ -1,9 +1,13 @@
-use std::{
-    collections::{HashMap, HashSet},
-    ops::Add,
+use std::ops::Add;
+
+use crate::util::{
+    config,
+    dsu::{label_components, Connectivity},
+    hash::{FastMap, FastSet},
+    input::{self, Block},
+    parse,
 };
 
-#[allow(dead_code)]
 static TEST: &str = include_str!("../data/d12_test");
 static INPUT: &str = include_str!("../data/d12");
 
@@ -95,9 +99,9 @@ struct Region {
 
 impl Region {
     fn determine_price(&self) -> usize {
-        let mut price_per_position: HashMap<_, _> =
+        let mut price_per_position: FastMap<_, _> =
             self.positions.iter().copied().map(|p| (p, 4)).collect();
-        let mut seen = HashSet::new();
+        let mut seen = FastSet::default();
 
         for position in &self.positions {
             seen.insert(position);
@@ -124,9 +128,9 @@ impl Region {
         //    - no: change direction to down.
         //  when rotating counter clockwise go to the next tile, and increment
         //  when rotating clockwise change state and increment
-        let lookup: HashSet<_> = self.positions.iter().copied().collect();
+        let lookup: FastSet<_> = self.positions.iter().copied().collect();
         let mut number_of_sides = 0;
-        let mut seen = HashSet::new();
+        let mut seen = FastSet::default();
 
         for start_position in self.positions.iter().copied() {
             for start_direction in is_edge_position(&start_position, &lookup)
@@ -151,8 +155,8 @@ impl Region {
 fn determine_number_of_edges(
     mut current_direction: Direction,
     mut current_position: Point<isize>,
-    seen: &mut HashSet<(Point<isize>, Direction)>,
-    lookup: &HashSet<Point<isize>>,
+    seen: &mut FastSet<(Point<isize>, Direction)>,
+    lookup: &FastSet<Point<isize>>,
 ) -> usize {
     let mut number_of_sides = 0;
 
@@ -181,7 +185,7 @@ fn determine_number_of_edges(
 
 fn is_edge_position(
     position: &Point<isize>,
-    lookup: &HashSet<Point<isize>>,
+    lookup: &FastSet<Point<isize>>,
 ) -> [Option<Direction>; 4] {
     let mut possible_directions = [None, None, None, None];
     for (i, direction) in [
@@ -201,64 +205,41 @@ fn is_edge_position(
     possible_directions
 }
 
+fn input() -> &'static str {
+    input::prepare(
+        "d12",
+        config::input("d12", INPUT, TEST),
+        &[Block::Grid("A-Z")],
+    )
+}
+
 fn parse_input(input: &str) -> Vec<Vec<char>> {
-    input.lines().map(|line| line.chars().collect()).collect()
+    parse::char_grid(input).unwrap_or_else(|e| panic!("Invalid garden map at {e}"))
 }
 
 fn determine_regions(map: Vec<Vec<char>>) -> Vec<Region> {
     let height = map.len();
     let width = map.first().map(|row| row.len()).unwrap_or(0);
-    let mut seen: HashSet<Point<isize>> = HashSet::new();
-    let mut regions = Vec::new();
-
-    for y in 0..height {
-        for x in 0..width {
-            match (x, y).try_into() {
-                Ok(position) if !seen.contains(&position) => {
-                    regions.push(depth_first_search(position, &mut seen, &map));
-                }
-                _ => continue,
-            }
-        }
-    }
+    let components = label_components(width, height, Connectivity::Four, |(x1, y1), (x2, y2)| {
+        map[y1][x1] == map[y2][x2]
+    });
 
-    regions
-}
+    components
+        .cells()
+        .into_iter()
+        .map(|cells| {
+            let (x, y) = cells[0];
+            let positions = cells
+                .into_iter()
+                .filter_map(|position| position.try_into().ok())
+                .collect();
 
-fn depth_first_search(
-    start_position: Point<isize>,
-    seen: &mut HashSet<Point<isize>>,
-    map: &[Vec<char>],
-) -> Region {
-    let mut queue = vec![start_position];
-    let tag = map
-        .get(start_position.y as usize)
-        .unwrap()
-        .get(start_position.x as usize)
-        .copied()
-        .unwrap();
-    let mut positions = Vec::new();
-
-    while let Some(current_position) = queue.pop() {
-        if !seen.contains(&current_position) {
-            seen.insert(current_position);
-            positions.push(current_position);
-
-            for next_position in get_adjacent_positions(&current_position) {
-                if let Some(next_tag) = map
-                    .get(next_position.y as usize)
-                    .and_then(|p| p.get(next_position.x as usize))
-                    .copied()
-                {
-                    if next_tag == tag {
-                        queue.push(next_position);
-                    }
-                }
+            Region {
+                positions,
+                tag: map[y][x],
             }
-        }
-    }
-
-    Region { positions, tag }
+        })
+        .collect()
 }
 
 fn get_adjacent_positions(position: &Point<isize>) -> [Point<isize>; 4] {
@@ -271,14 +252,14 @@ fn get_adjacent_positions(position: &Point<isize>) -> [Point<isize>; 4] {
 }
 
 pub fn solve_1() -> usize {
-    determine_regions(parse_input(INPUT))
+    determine_regions(parse_input(input()))
         .into_iter()
         .map(|region| region.determine_price())
         .sum()
 }
 
 pub fn solve_2() -> usize {
-    determine_regions(parse_input(INPUT))
+    determine_regions(parse_input(input()))
         .into_iter()
         .map(|region| region.determine_price_surrounding())
         .sum()
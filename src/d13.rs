@@ -1,4 +1,10 @@
-#[allow(dead_code)]
+use crate::util::{
+    config,
+    input::{self, Block},
+    math, parse,
+    trace::{self, Event, Value},
+};
+
 static TEST: &str = include_str!("../data/d13_test");
 
 static INPUT: &str = include_str!("../data/d13");
@@ -10,22 +16,6 @@ struct Button {
     y: isize,
 }
 
-impl From<&str> for Button {
-    fn from(value: &str) -> Self {
-        let numbers: Vec<_> = value.split_whitespace().skip(2).take(2).collect();
-        let x = numbers
-            .first()
-            .and_then(|x_str| x_str[2..x_str.len() - 1].parse().ok())
-            .expect("Button string has unexpected format");
-        let y = numbers
-            .get(1)
-            .and_then(|y_str| y_str[2..].parse().ok())
-            .expect("Button string has unexpected format");
-
-        Button { x, y }
-    }
-}
-
 struct Machine {
     button_a: Button, // a d
     button_b: Button, // b e
@@ -33,83 +23,93 @@ struct Machine {
     prize_y: isize,
 }
 
+struct Solved {
+    prize: (isize, isize),
+    presses: Option<(isize, isize)>,
+}
+
+impl Event for Solved {
+    const KIND: &'static str = "machine";
+
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("prize", vec![self.prize.0, self.prize.1].into()),
+            ("a_presses", self.presses.map(|(a, _)| a).into()),
+            ("b_presses", self.presses.map(|(_, b)| b).into()),
+        ]
+    }
+}
+
 impl Machine {
     fn solve(&self) -> Option<(isize, isize)> {
-        self.solve_y().and_then(|y| self.solve_x(y))
+        let presses = self.solve_presses();
+        trace::emit(|| Solved {
+            prize: (self.prize_x, self.prize_y),
+            presses,
+        });
+        presses
     }
 
-    fn solve_y(&self) -> Option<isize> {
-        let denominator = self.button_b.y * self.button_a.x - self.button_b.x * self.button_a.y;
-        // unsolvable
-        if denominator == 0 {
-            None
-        } else {
-            let numerator = self.prize_y * self.button_a.x - self.button_a.y * self.prize_x;
-            if numerator % denominator != 0 {
-                None
-            } else {
-                Some(numerator / denominator)
-            }
-        }
+    // Solves a * button_a + b * button_b = prize for the number of presses a and b.
+    fn solve_presses(&self) -> Option<(isize, isize)> {
+        let [a_presses, b_presses] = math::solve_linear(
+            [
+                [self.button_a.x as i64, self.button_b.x as i64],
+                [self.button_a.y as i64, self.button_b.y as i64],
+            ],
+            [self.prize_x as i64, self.prize_y as i64],
+        )?;
+
+        Some((
+            a_presses.to_integer()? as isize,
+            b_presses.to_integer()? as isize,
+        ))
     }
+}
 
-    fn solve_x(&self, y: isize) -> Option<(isize, isize)> {
-        let denominator = self.button_a.x;
-        if denominator == 0 {
-            None
-        } else {
-            let numerator = self.prize_x - self.button_b.x * y;
-
-            if numerator % denominator != 0 {
-                None
-            } else {
-                Some((numerator / denominator, y))
-            }
-        }
-    }
+fn input() -> &'static str {
+    input::prepare(
+        "d13",
+        config::input("d13", INPUT, TEST),
+        &[Block::Lines("0-9A-Za-z:+=, ")],
+    )
 }
 
-fn parse_input(input: &str) -> Vec<Machine> {
-    let mut lines = input.lines();
+fn parse_input(input: &str) -> Result<Vec<Machine>, parse::ParseError> {
     let mut machines = Vec::new();
 
-    while let Some(button_a_str) = lines.next() {
-        let button_a = button_a_str.into();
-        let button_b = lines
-            .next()
-            .expect("Input has invalid format. Expect Button B.")
-            .into();
-        let mut prize_parts = lines
-            .next()
-            .expect("Input has invalid format. Expect Prize.")
-            .split_whitespace()
-            .skip(1)
-            .take(2);
-
-        let x = prize_parts
-            .next()
-            .and_then(|x_str| x_str[2..x_str.len() - 1].parse().ok())
-            .expect("Prize string has invald format");
-        let y = prize_parts
-            .next()
-            .and_then(|y_str| y_str[2..].parse().ok())
-            .expect("Prize string has invald format");
+    for section in parse::sections(input) {
+        // Both buttons and the prize are an X and a Y value.
+        let values = section.lines(|line| {
+            <[isize; 2]>::try_from(parse::ints(line)?)
+                .map_err(|_| parse::ParseError::new(1, 1, "expected an X and a Y value"))
+        })?;
+
+        let [[a_x, a_y], [b_x, b_y], [prize_x, prize_y]] = values[..] else {
+            return Err(parse::ParseError::new(
+                section.first_line,
+                1,
+                "expected two buttons and a prize",
+            ));
+        };
 
         machines.push(Machine {
-            button_a,
-            button_b,
-            prize_x: x,
-            prize_y: y,
+            button_a: Button { x: a_x, y: a_y },
+            button_b: Button { x: b_x, y: b_y },
+            prize_x,
+            prize_y,
         });
-
-        let _ = lines.next();
     }
 
-    machines
+    Ok(machines)
+}
+
+fn machines() -> Vec<Machine> {
+    parse_input(input()).unwrap_or_else(|e| panic!("Invalid machine at {e}"))
 }
 
 pub fn solve_1() -> isize {
-    parse_input(INPUT)
+    machines()
         .iter()
         .filter_map(|machine| machine.solve())
         .filter(|(a_presses, b_presses)| *a_presses <= 100 && *b_presses <= 100)
@@ -119,11 +119,13 @@ pub fn solve_1() -> isize {
 
 #[allow(clippy::manual_inspect)]
 pub fn solve_2() -> isize {
-    parse_input(INPUT)
+    let increase = config::param("d13", "part_2_increase", PART_2_INCREASE);
+
+    machines()
         .iter_mut()
         .map(|machine| {
-            machine.prize_x += PART_2_INCREASE;
-            machine.prize_y += PART_2_INCREASE;
+            machine.prize_x += increase;
+            machine.prize_y += increase;
 
             machine
         })
@@ -131,3 +133,25 @@ pub fn solve_2() -> isize {
         .map(|(a_presses, b_presses)| a_presses * 3 + b_presses)
         .sum()
 }
+
+#[test]
+fn test_parse_errors() {
+    let machine = "Button A: X+94, Y+34\nButton B: X+22, Y+67\nPrize: X=8400, Y=5400\n";
+    assert_eq!(parse_input(machine).unwrap().len(), 1);
+
+    let error = |input: &str| parse_input(input).err().map(|e| e.to_string());
+    assert_eq!(
+        error(&format!(
+            "{machine}\nButton A: X+1, Y+99999999999999999999\n"
+        )),
+        Some("5:17: `+99999999999999999999` is not a valid isize".to_string())
+    );
+    assert_eq!(
+        error(&format!("{machine}\nButton A: X+1\n")),
+        Some("5:1: expected an X and a Y value".to_string())
+    );
+    assert_eq!(
+        error(&format!("{machine}\n{}", &machine[..42])),
+        Some("5:1: expected two buttons and a prize".to_string())
+    );
+}
@@ -1,14 +1,21 @@
 use std::{
-    collections::HashSet,
+    fmt::Display,
     ops::{Add, AddAssign, Rem},
 };
 
-#[allow(dead_code)]
+use crate::util::{
+    config,
+    input::{self, Block},
+    math, parse,
+    sim::{Periodic, Simulation},
+    trace::{self, Event, Value},
+};
+
 static TEST: &str = include_str!("../data/d14_test");
 
 static INPUT: &str = include_str!("../data/d14");
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Vec2D {
     x: isize,
     y: isize,
@@ -20,20 +27,6 @@ impl Vec2D {
     }
 }
 
-impl From<&str> for Vec2D {
-    fn from(value: &str) -> Self {
-        let mut parts = value.split(',');
-
-        match (parts.next(), parts.next()) {
-            (Some(x), Some(y)) => match (x.parse(), y.parse()) {
-                (Ok(x), Ok(y)) => Self { x, y },
-                _ => panic!("Input string contains invalid number"),
-            },
-            _ => panic!("Input string has invalid value"),
-        }
-    }
-}
-
 impl Add for Vec2D {
     type Output = Self;
 
@@ -122,6 +115,63 @@ impl Robot {
     }
 }
 
+struct Room {
+    robots: Vec<Robot>,
+    map_size: Vec2D,
+}
+
+impl Simulation for Room {
+    fn step(&mut self) -> bool {
+        for robot in &mut self.robots {
+            robot.move_forward(&self.map_size);
+        }
+        true
+    }
+}
+
+impl Periodic for Room {
+    type Fingerprint = Vec<Vec2D>;
+    type Snapshot = Vec<Robot>;
+
+    fn fingerprint(&self) -> Vec<Vec2D> {
+        self.robots.iter().map(|robot| robot.position).collect()
+    }
+
+    fn snapshot(&self) -> Vec<Robot> {
+        self.robots.clone()
+    }
+
+    fn restore(&mut self, robots: &Vec<Robot>) {
+        self.robots.clone_from(robots);
+    }
+}
+
+/// The room at the second the tree shows up.
+struct Tree {
+    second: usize,
+    /// After how many seconds the robots are back where they started.
+    period: Option<usize>,
+    picture: String,
+}
+
+impl Event for Tree {
+    const KIND: &'static str = "tree";
+
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("second", self.second.into()),
+            ("period", self.period.into()),
+            ("picture", self.picture.as_str().into()),
+        ]
+    }
+}
+
+impl Display for Room {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", print_map(&self.robots, &self.map_size))
+    }
+}
+
 fn print_map(robots: &[Robot], map_size: &Vec2D) -> String {
     let mut map: Vec<Vec<char>> = (0..map_size.y)
         .map(|_| (0..map_size.x).map(|_| '.').collect())
@@ -159,40 +209,55 @@ fn divide_into_quadrants(map_size: &Vec2D) -> [(Vec2D, Vec2D); 4] {
     ]
 }
 
-fn parse_input(input: &str) -> Vec<Robot> {
-    let mut robots = Vec::new();
-    for line in input.lines() {
-        let mut robot_str = line.split_whitespace();
-        let robot = match (robot_str.next(), robot_str.next()) {
-            (Some(position), Some(velocity)) => Robot {
-                position: position[2..].into(),
-                velocity: velocity[2..].into(),
-            },
-            _ => panic!("Line with robot has invalid format!"),
-        };
+fn input() -> &'static str {
+    input::prepare(
+        "d14",
+        config::input("d14", INPUT, TEST),
+        &[Block::Lines("0-9pv=, -")],
+    )
+}
 
-        robots.push(robot);
-    }
+fn map_size() -> Vec2D {
+    Vec2D::new(
+        config::param("d14", "width", 101),
+        config::param("d14", "height", 103),
+    )
+}
 
-    robots
+fn parse_input(input: &str) -> Vec<Robot> {
+    parse::lines(input, |line| {
+        let numbers = parse::ints(line)?;
+        match numbers[..] {
+            [px, py, vx, vy] => Ok(Robot {
+                position: Vec2D::new(px, py),
+                velocity: Vec2D::new(vx, vy),
+            }),
+            _ => Err(parse::ParseError::new(1, 1, "expected `p=x,y v=x,y`")),
+        }
+    })
+    .unwrap_or_else(|e| panic!("Line with robot has invalid format at {e}"))
 }
 
 pub fn solve_1() -> usize {
-    let mut robots = parse_input(INPUT);
-    let map_size = Vec2D::new(101, 103);
-    let quadrants = divide_into_quadrants(&map_size);
-    for _ in 0..100 {
-        for robot in &mut robots {
-            robot.move_forward(&map_size);
+    let mut room = Room {
+        robots: parse_input(input()),
+        map_size: map_size(),
+    };
+    let quadrants = divide_into_quadrants(&room.map_size);
+    let mut seconds = config::param("d14", "seconds", 100);
+
+    // The robots repeat after at most width * height seconds, so skip whole cycles.
+    if seconds > (room.map_size.x * room.map_size.y) as usize {
+        if let Some(cycle) = room.find_cycle() {
+            seconds = cycle.equivalent_step(seconds);
         }
-
-        print_map(&robots, &map_size);
     }
+    room.run_n(seconds);
 
     quadrants
         .into_iter()
         .map(|(top_left, bottom_right)| {
-            robots
+            room.robots
                 .iter()
                 .filter(|robot| robot.is_in_area(top_left, bottom_right))
                 .count()
@@ -201,33 +266,81 @@ pub fn solve_1() -> usize {
         .product()
 }
 
+// Robots move independently along both axes, so the x coordinates repeat every
+// `map_size.x` seconds and the y coordinates every `map_size.y` seconds. The tree is
+// drawn when the robots are clustered, so find the second with the smallest spread on
+// each axis and combine both with the chinese remainder theorem.
 pub fn solve_2() -> usize {
-    let mut robots = parse_input(INPUT);
-    let map_size = Vec2D::new(101, 103);
-
-    let mut seen = HashSet::<String>::new();
-
-    for _i in 1.. {
-        for robot in &mut robots {
-            robot.move_forward(&map_size);
+    let robots = parse_input(input());
+    let map_size = map_size();
+
+    let second_x = most_clustered_second(&robots, map_size.x, |v| v.x);
+    let second_y = most_clustered_second(&robots, map_size.y, |v| v.y);
+
+    let second = math::crt(&[
+        (second_x as i64, map_size.x as i64),
+        (second_y as i64, map_size.y as i64),
+    ])
+    .map(|(second, _)| second as usize)
+    .expect("Map width and height have to be coprime");
+
+    trace::emit(|| {
+        let mut room = Room { robots, map_size };
+        let period = room.find_cycle().map(|cycle| cycle.length);
+        room.run_n(second);
+        Tree {
+            second,
+            period,
+            picture: room.to_string(),
         }
+    });
 
-        let map = print_map(&robots, &map_size);
-        /*
-                if i >= 432 && (i - 432) % 101 == 0 {
-                    println!("{map}");
-                    println!("{i}");
-                }
-        */
-        if !seen.insert(map) {
-            break;
-        }
-    }
+    second
+}
 
-    7603
+fn most_clustered_second(robots: &[Robot], period: isize, axis: impl Fn(&Vec2D) -> isize) -> isize {
+    let n = robots.len() as isize;
+
+    (0..period)
+        .min_by_key(|second| {
+            let (sum, sum_of_squares) = robots
+                .iter()
+                .map(|robot| {
+                    (axis(&robot.position) + axis(&robot.velocity) * second).rem_euclid(period)
+                })
+                .fold((0, 0), |(sum, sum_of_squares), p| {
+                    (sum + p, sum_of_squares + p * p)
+                });
+
+            // n² times the variance, which is enough for comparing
+            n * sum_of_squares - sum * sum
+        })
+        .unwrap_or(0)
 }
 
 #[test]
 fn test() {
     println!("{}", solve_2());
 }
+
+#[test]
+fn test_cycle() {
+    use crate::util::sim::Cycle;
+
+    // Every robot is back after `width` seconds on x and `height` seconds on y.
+    let mut room = Room {
+        robots: parse_input(TEST),
+        map_size: Vec2D::new(11, 7),
+    };
+    let cycle = Cycle {
+        start: 0,
+        length: 77,
+    };
+    assert_eq!(room.find_cycle(), Some(cycle));
+    assert_eq!(room.find_cycle_floyd(), Some(cycle));
+
+    let start = room.fingerprint();
+    assert_eq!(room.run_until(|room| room.fingerprint() == start), Some(0));
+    room.step();
+    assert_eq!(room.run_until(|room| room.fingerprint() == start), Some(76));
+}
@@ -3,7 +3,13 @@ use std::{
     ops::{Add, AddAssign},
 };
 
-#[allow(dead_code)]
+use crate::util::{
+    config,
+    input::{self, Block},
+    parse,
+    sim::Simulation,
+};
+
 static TEST: &str = include_str!("../data/d15_test");
 static INPUT: &str = include_str!("../data/d15");
 
@@ -401,29 +407,61 @@ impl Robot<Tile2> {
     }
 }
 
-fn parse_input(input: &str) -> (Map<Tile1>, Robot<Tile1>, Vec<Direction>) {
-    let width = input.find('\n').expect("Input has no linebreaks");
-    let mut iter = input.lines().enumerate();
-    let mut tiles = Vec::new();
-    let mut robot = Robot::<Tile1>::new(0, 0, Direction::Up);
-    let mut height = 0;
-
-    for (y, line) in iter.by_ref() {
-        if line.is_empty() {
-            height = y;
-            break;
-        } else {
-            for (x, tile) in line.chars().enumerate() {
-                if tile == '@' {
-                    robot = Robot::<Tile1>::new(x, y, Direction::Up);
-                }
-                tiles.push(tile.into());
-            }
-        }
+/// The robot working through its list of moves, one move per step.
+struct Warehouse<T: Tile> {
+    map: Map<T>,
+    robot: Robot<T>,
+    moves: std::vec::IntoIter<Direction>,
+}
+
+impl Simulation for Warehouse<Tile1> {
+    fn step(&mut self) -> bool {
+        let Some(direction) = self.moves.next() else {
+            return false;
+        };
+        self.robot.make_move(&mut self.map, direction);
+        true
+    }
+}
+
+impl Simulation for Warehouse<Tile2> {
+    fn step(&mut self) -> bool {
+        let Some(direction) = self.moves.next() else {
+            return false;
+        };
+        self.robot.make_move(&mut self.map, direction);
+        true
     }
+}
+
+fn input() -> &'static str {
+    input::prepare(
+        "d15",
+        config::input("d15", INPUT, TEST),
+        &[Block::Grid("#.O@"), Block::Lines("<>^v")],
+    )
+}
+
+fn parse_input(input: &str) -> (Map<Tile1>, Robot<Tile1>, Vec<Direction>) {
+    let [map_section, moves_section] = parse::sections(input)[..] else {
+        panic!("Input has to consist of a map and a list of moves");
+    };
+
+    let (tiles, width, height) =
+        parse::grid(map_section.text, Some).unwrap_or_else(|e| panic!("Invalid map at {e}"));
+
+    let robot_index = tiles
+        .iter()
+        .position(|tile| *tile == '@')
+        .expect("Map contains no robot");
+    let robot = Robot::<Tile1>::new(robot_index % width, robot_index / width, Direction::Up);
 
-    let directions = iter
-        .flat_map(|line| line.1.chars())
+    let tiles = tiles.into_iter().map(|tile| tile.into()).collect();
+
+    let directions = moves_section
+        .text
+        .lines()
+        .flat_map(|line| line.chars())
         .map(|direction| direction.into())
         .collect();
 
@@ -435,28 +473,30 @@ fn parse_input(input: &str) -> (Map<Tile1>, Robot<Tile1>, Vec<Direction>) {
 }
 
 pub fn solve_1() -> usize {
-    let (mut map, mut robot, directions) = parse_input(INPUT);
-
-    for direction in directions {
-        robot.make_move(&mut map, direction);
-    }
+    let (map, robot, directions) = parse_input(input());
+    let mut warehouse = Warehouse {
+        map,
+        robot,
+        moves: directions.into_iter(),
+    };
 
-    map.calculate_score()
+    warehouse.run_to_end();
+    warehouse.map.calculate_score()
 }
 
 pub fn solve_2() -> usize {
-    let (map, robot, directions) = parse_input(INPUT);
+    let (map, robot, directions) = parse_input(input());
 
-    let mut map: Map<Tile2> = map.into();
-    let mut robot: Robot<Tile2> = Robot::<Tile2> {
-        position: Point::new(robot.position.x * 2, robot.position.y),
-        direction: robot.direction,
-        _boo: PhantomData,
+    let mut warehouse = Warehouse {
+        map: Map::<Tile2>::from(map),
+        robot: Robot::<Tile2> {
+            position: Point::new(robot.position.x * 2, robot.position.y),
+            direction: robot.direction,
+            _boo: PhantomData,
+        },
+        moves: directions.into_iter(),
     };
 
-    for direction in directions {
-        robot.make_move(&mut map, direction);
-    }
-
-    map.calculate_score()
+    warehouse.run_to_end();
+    warehouse.map.calculate_score()
 }
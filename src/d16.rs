@@ -1,9 +1,17 @@
 use std::{
-    collections::{BinaryHeap, HashMap, HashSet},
+    fmt::Display,
     ops::{Add, AddAssign},
 };
 
-#[allow(dead_code)]
+use crate::util::{
+    config,
+    dot::{DotOptions, DotWriter},
+    hash::{FastMap, FastSet},
+    input::{self, Block},
+    search::{self, OptimalPaths, SearchProblem},
+    span::span,
+};
+
 static TEST: &str = include_str!("../data/d16_test");
 static INPUT: &str = include_str!("../data/d16");
 
@@ -28,6 +36,17 @@ impl Dir {
     }
 }
 
+impl From<Dir> for char {
+    fn from(value: Dir) -> Self {
+        match value {
+            Dir::Up => '^',
+            Dir::Right => '>',
+            Dir::Down => 'v',
+            Dir::Left => '<',
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Point {
     x: usize,
@@ -125,22 +144,6 @@ impl<N, E> DiGraph<N, E> {
         Some(self.edges.len() - 1)
     }
 
-    fn get_neighbours(&self, node_index: usize) -> Vec<usize> {
-        let mut neighbours = Vec::new();
-        let Some(node) = self.nodes.get(node_index) else {
-            return Vec::new();
-        };
-
-        let mut edge_index_opt = node.next_edge;
-        while let Some(edge_index) = edge_index_opt {
-            let edge = &self.edges[edge_index];
-            neighbours.push(edge.tail);
-            edge_index_opt = edge.next_edge;
-        }
-
-        neighbours
-    }
-
     fn get_edge(&self, head: usize, tail: usize) -> Option<usize> {
         let mut edge_index_opt = self.nodes.get(head)?.next_edge;
 
@@ -165,6 +168,27 @@ impl<N, E> DiGraph<N, E> {
     }
 }
 
+impl<N: Display, E: Display> DiGraph<N, E> {
+    fn to_dot(&self, options: &DotOptions) -> String {
+        let mut dot = DotWriter::digraph("maze", options);
+
+        for (head, node) in self.nodes.iter().enumerate() {
+            dot.node(head, || node.weight.to_string());
+        }
+
+        for (head, node) in self.nodes.iter().enumerate() {
+            let mut edge_index_opt = node.next_edge;
+            while let Some(edge_index) = edge_index_opt {
+                let edge = &self.edges[edge_index];
+                dot.edge(head, edge.tail, || edge.weight.to_string());
+                edge_index_opt = edge.next_edge;
+            }
+        }
+
+        dot.finish()
+    }
+}
+
 #[derive(Debug)]
 struct Node<T> {
     weight: T,
@@ -214,12 +238,13 @@ fn get_next(node: &State, cost: usize, map: &Map) -> Vec<(State, usize)> {
 }
 
 fn build_graph(map: &Map) -> DiGraph<State, usize> {
+    let _s = span("build_graph");
     let start_node = State::new(map.get_start(), Dir::Right);
     let end_point = map.get_end();
     let mut graph = DiGraph::<State, usize>::new();
     let start_index = graph.add_node(start_node);
-    let mut seen_edges = HashSet::new();
-    let mut seen_nodes = HashMap::from([(start_node, start_index)]);
+    let mut seen_edges = FastSet::default();
+    let mut seen_nodes = FastMap::from_iter([(start_node, start_index)]);
     let mut queue = vec![start_index];
 
     while let Some(origin_index) = queue.pop() {
@@ -269,76 +294,65 @@ impl State {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-struct WeightedState {
-    node_index: usize,
-    cost: usize,
-}
-
-impl WeightedState {
-    fn new(node_index: usize, cost: usize) -> Self {
-        Self { node_index, cost }
-    }
-}
-
-impl Ord for WeightedState {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.cost.cmp(&other.cost).reverse()
+impl Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "({},{}) {}",
+            self.point.x,
+            self.point.y,
+            char::from(self.dir)
+        )
     }
 }
 
-impl PartialOrd for WeightedState {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
+struct Maze<'a> {
+    graph: &'a DiGraph<State, usize>,
+    end: Point,
 }
 
-fn dijkstra_graph(graph: &DiGraph<State, usize>, end: Point) -> (usize, usize, Vec<Vec<usize>>) {
-    let mut queue = BinaryHeap::from([WeightedState::new(0, 0)]);
-    let mut min = usize::MAX;
-    let mut costs = vec![usize::MAX; graph.nodes.len()];
-    let mut predecessor_lookup = vec![Vec::new(); graph.nodes.len()];
-    let mut end_index = usize::MAX;
+impl SearchProblem for Maze<'_> {
+    type State = usize;
 
-    while let Some(WeightedState { node_index, cost }) = queue.pop() {
-        let node = graph.get_node_weight(node_index).unwrap();
+    fn start(&self) -> Vec<usize> {
+        vec![0]
+    }
 
-        if cost > min {
-            break;
-        } else if node.point == end {
-            min = cost;
-            end_index = node_index;
-            continue;
+    fn successors(&self, node_index: &usize, next: &mut Vec<(usize, usize)>) {
+        let mut edge_index_opt = self.graph.nodes[*node_index].next_edge;
+        while let Some(edge_index) = edge_index_opt {
+            let edge = &self.graph.edges[edge_index];
+            next.push((edge.tail, edge.weight));
+            edge_index_opt = edge.next_edge;
         }
+    }
 
-        for neighbor in graph.get_neighbours(node_index) {
-            let edge_index = graph.get_edge(node_index, neighbor).unwrap();
-            let next_cost = cost + graph.get_edge_weight(edge_index).unwrap();
-
-            if costs[neighbor] > next_cost {
-                costs[neighbor] = next_cost;
-                predecessor_lookup[neighbor] = vec![node_index];
-                queue.push(WeightedState::new(neighbor, next_cost));
-            } else if costs[neighbor] == next_cost {
-                predecessor_lookup[neighbor].push(node_index);
-                queue.push(WeightedState::new(neighbor, next_cost));
-            }
-        }
+    fn is_goal(&self, node_index: &usize) -> bool {
+        self.graph.nodes[*node_index].weight.point == self.end
     }
+}
+
+fn lowest_score(graph: &DiGraph<State, usize>, end: Point) -> usize {
+    let _s = span("lowest_score");
+    let result = search::dijkstra(&Maze { graph, end });
+    result.stats.report("lowest_score");
+    result.cost().expect("End should be reachable")
+}
 
-    (min, end_index, predecessor_lookup)
+fn shortest_paths(graph: &DiGraph<State, usize>, end: Point) -> OptimalPaths<usize> {
+    let _s = span("shortest_paths");
+    let paths = search::all_optimal_paths(&Maze { graph, end }).expect("End should be reachable");
+    paths.stats.report("shortest_paths");
+    paths
 }
 
-fn count_nodes_graph(
-    graph: &DiGraph<State, usize>,
-    end_index: usize,
-    predecessor_lookup: Vec<Vec<usize>>,
-) -> usize {
+fn count_nodes_graph(graph: &DiGraph<State, usize>, paths: &OptimalPaths<usize>) -> usize {
+    let _s = span("count_nodes_graph");
     let mut number_of_positions = 0;
     let mut seen_edges = vec![false; graph.edges.len()];
     let mut seen_nodes = vec![false; graph.nodes.len()];
 
-    let mut queue = vec![end_index];
+    let mut queue = vec![paths.goals[0]];
 
     while let Some(node_index) = queue.pop() {
         if seen_nodes[node_index] {
@@ -346,16 +360,17 @@ fn count_nodes_graph(
         } else {
             seen_nodes[node_index] = true;
 
-            let predecessors = predecessor_lookup[node_index]
-                .iter()
-                .collect::<HashSet<_>>();
+            let predecessors = paths
+                .predecessors
+                .get(&node_index)
+                .map_or(&[][..], Vec::as_slice);
 
-            for predecessor in &predecessors {
-                let edge = graph.get_edge(**predecessor, node_index).unwrap();
+            for predecessor in predecessors {
+                let edge = graph.get_edge(*predecessor, node_index).unwrap();
                 if !seen_edges[edge] {
                     seen_edges[edge] = true;
                     number_of_positions += graph.get_edge_weight(edge).unwrap() % 1000;
-                    queue.push(**predecessor);
+                    queue.push(*predecessor);
                 }
             }
 
@@ -368,7 +383,16 @@ fn count_nodes_graph(
     number_of_positions + 1
 }
 
+fn input() -> &'static str {
+    input::prepare(
+        "d16",
+        config::input("d16", INPUT, TEST),
+        &[Block::Grid("#.SE")],
+    )
+}
+
 fn parse(input: &str) -> Map {
+    let _s = span("parse");
     let width = input.find('\n').unwrap_or(0);
     let lines = input.lines().collect::<Vec<&str>>();
     let height = lines.len();
@@ -382,15 +406,27 @@ fn parse(input: &str) -> Map {
 }
 
 pub fn solve_1() -> usize {
-    let map = parse(INPUT);
+    let map = parse(input());
     let graph = build_graph(&map);
-    let (min, _, _) = dijkstra_graph(&graph, map.get_end());
-    min
+    lowest_score(&graph, map.get_end())
 }
 
 pub fn solve_2() -> usize {
-    let map = parse(INPUT);
+    let map = parse(input());
+    let graph = build_graph(&map);
+    let paths = shortest_paths(&graph, map.get_end());
+    count_nodes_graph(&graph, &paths)
+}
+
+/// Renders the compressed junction graph of the maze, with all shortest paths highlighted.
+pub fn to_dot() -> String {
+    let map = parse(input());
     let graph = build_graph(&map);
-    let (_, end_index, lookup) = dijkstra_graph(&graph, map.get_end());
-    count_nodes_graph(&graph, end_index, lookup)
+    let paths = shortest_paths(&graph, map.get_end());
+    let options = DotOptions::new()
+        .with_node_labels()
+        .with_edge_weights()
+        .with_highlight(paths.moves());
+
+    graph.to_dot(&options)
 }
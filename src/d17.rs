@@ -1,3 +1,5 @@
+use crate::util::config;
+
 struct Machine {
     a: usize,
     b: usize,
@@ -9,20 +11,15 @@ struct Machine {
 
 impl Default for Machine {
     fn default() -> Self {
-        Self {
-            a: 30344604,
-            b: 0,
-            c: 0,
-            ip: 0,
-            chunk: vec![2, 4, 1, 1, 7, 5, 1, 5, 4, 5, 0, 3, 5, 5, 3, 0],
-            out: Vec::new(),
-        }
+        Self::new(
+            config::param("d17", "register_a", 30344604),
+            config::list("d17", "program", &Self::INIT_CHUNK),
+        )
     }
 }
 
 impl Machine {
     const INIT_CHUNK: [usize; 16] = [2, 4, 1, 1, 7, 5, 1, 5, 4, 5, 0, 3, 5, 5, 3, 0];
-    const CHUNK_LEN: usize = Self::INIT_CHUNK.len();
     const FN_TABLE: [fn(&mut Self); 8] = [
         Self::adv,
         Self::bxl,
@@ -34,10 +31,14 @@ impl Machine {
         Self::cdv,
     ];
 
-    fn new_set_a(a: usize) -> Self {
+    fn new(a: usize, chunk: Vec<usize>) -> Self {
         Self {
             a,
-            ..Self::default()
+            b: 0,
+            c: 0,
+            ip: 0,
+            chunk,
+            out: Vec::new(),
         }
     }
 
@@ -103,19 +104,20 @@ impl Machine {
     }
 
     fn find_lowest_value() -> Option<usize> {
-        Self::find_lowest_value_recurse(Self::CHUNK_LEN - 1, 0)
+        let chunk = Self::default().chunk;
+        Self::find_lowest_value_recurse(&chunk, chunk.len() - 1, 0)
     }
 
-    fn find_lowest_value_recurse(n: usize, i: usize) -> Option<usize> {
+    fn find_lowest_value_recurse(chunk: &[usize], n: usize, i: usize) -> Option<usize> {
         for a in i..i + 8 {
-            let mut m = Machine::new_set_a(a);
+            let mut m = Machine::new(a, chunk.to_vec());
             m.exec();
 
             if m.out[0] == m.chunk[n] {
                 if n == 0 {
                     return Some(a);
                 }
-                if let Some(res) = Self::find_lowest_value_recurse(n - 1, a << 3) {
+                if let Some(res) = Self::find_lowest_value_recurse(chunk, n - 1, a << 3) {
                     return Some(res);
                 }
             }
@@ -1,167 +1,211 @@
-use std::{
-    collections::{HashSet, VecDeque},
-    fmt::Display,
-    ops::{Add, AddAssign},
+use crate::util::{
+    bitgrid::BitGrid,
+    config,
+    dsu::DisjointSets,
+    input::{self, Block},
+    point::{Point, DIRS},
+    search::{self, SearchProblem},
 };
 
-#[allow(dead_code)]
 static TEST: &str = include_str!("../data/d18_test");
 static INPUT: &str = include_str!("../data/d18");
 
-#[allow(dead_code)]
-const SIZE_TEST: usize = 7;
 const SIZE: usize = 71;
-
-const DIRS: [Point; 4] = [
-    Point::new(0, usize::MAX),
-    Point::new(1, 0),
-    Point::new(usize::MAX, 0),
-    Point::new(0, 1),
-];
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Point {
-    x: usize,
-    y: usize,
+const FALLEN: usize = 1024;
+
+fn parse_configured() -> (impl Iterator<Item = Point>, BitGrid) {
+    parse(
+        input::prepare(
+            "d18",
+            config::input("d18", INPUT, TEST),
+            &[Block::Lines("0-9,")],
+        ),
+        config::param("d18", "size", SIZE),
+        config::param("d18", "fallen", FALLEN),
+    )
 }
 
-impl Point {
-    const fn new(x: usize, y: usize) -> Self {
-        Self { x, y }
+fn parse(input: &str, size: usize, fallen: usize) -> (impl Iterator<Item = Point> + '_, BitGrid) {
+    fn my_filter(line: &str) -> Option<Point> {
+        line.try_into().ok()
     }
-}
 
-impl TryFrom<&str> for Point {
-    type Error = ();
-    fn try_from(input: &str) -> Result<Self, Self::Error> {
-        let delim = input.find(',').ok_or(())?;
-        let x = input[..delim].parse::<usize>().map_err(|_| ())?;
-        let y = input[delim + 1..].parse::<usize>().map_err(|_| ())?;
+    let mut blocks = input.lines().filter_map(my_filter);
+
+    let mut map = BitGrid::new(size, size);
+    map.fill();
 
-        Ok(Point::new(x, y))
+    for point in (&mut blocks).take(fallen) {
+        map.unset(point.x, point.y);
     }
+
+    (blocks, map)
 }
 
-impl Add for Point {
-    type Output = Point;
+struct Memory<'a> {
+    map: &'a BitGrid,
+    end: Point,
+}
+
+impl SearchProblem for Memory<'_> {
+    type State = Point;
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Point::new(self.x.wrapping_add(rhs.x), self.y.wrapping_add(rhs.y))
+    fn start(&self) -> Vec<Point> {
+        vec![Point::new(0, 0)]
     }
-}
 
-impl AddAssign for Point {
-    fn add_assign(&mut self, rhs: Self) {
-        self.x = self.x.wrapping_add(rhs.x);
-        self.y = self.y.wrapping_add(rhs.y);
+    fn successors(&self, current: &Point, next: &mut Vec<(Point, usize)>) {
+        next.extend(
+            DIRS.into_iter()
+                .map(|dir| *current + dir)
+                .filter(|next| self.map.get(next.x, next.y))
+                .map(|next| (next, 1)),
+        );
     }
-}
 
-impl Display for Point {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{},{}", self.x, self.y)
+    fn is_goal(&self, point: &Point) -> bool {
+        *point == self.end
     }
-}
 
-struct Map<const N: usize> {
-    tiles: [[bool; N]; N],
+    fn heuristic(&self, point: &Point) -> usize {
+        (self.end.x - point.x) + (self.end.y - point.y)
+    }
 }
 
-impl<const N: usize> Display for Map<N> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut s = String::new();
-        for row in self.tiles {
-            s.push_str(
-                &row.iter()
-                    .map(|tile| if *tile { '.' } else { '#' })
-                    .collect::<String>(),
-            );
-            s.push('\n');
-        }
+fn shortest_path(map: &BitGrid) -> Option<usize> {
+    let end = Point::new(map.width() - 1, map.height() - 1);
+    let result = search::astar(&Memory { map, end });
+    result.stats.report("shortest_path");
+    result.cost()
+}
 
-        write!(f, "{}", s)
-    }
+pub fn solve_1() -> usize {
+    let (_, map) = parse_configured();
+    shortest_path(&map).unwrap()
 }
 
-impl<const N: usize> Map<N> {
-    fn new(tiles: [[bool; N]; N]) -> Self {
-        Self { tiles }
+// Instead of searching for a path after every fallen byte, let all bytes fall and then
+// remove them again in reverse order, joining each freed tile with its free neighbours.
+// The first byte whose removal connects start and end is the one that cut them off.
+fn first_blocking(blocks: &[Point], size: usize) -> Option<Point> {
+    let index = |Point { x, y }: Point| y * size + x;
+    let start = Point::new(0, 0);
+    let end = Point::new(size - 1, size - 1);
+
+    // A tile is only free again once every byte which fell onto it is removed.
+    let mut fallen = vec![0usize; size * size];
+    for block in blocks {
+        fallen[index(*block)] += 1;
     }
 
-    fn get(&self, Point { x, y }: Point) -> bool {
-        if x < N && y < N {
-            self.tiles[y][x]
-        } else {
-            false
+    let mut map = BitGrid::new(size, size);
+    let mut sets = DisjointSets::new(size * size);
+
+    let free = |map: &mut BitGrid, point: Point, sets: &mut DisjointSets| {
+        map.set(point.x, point.y);
+        for dir in DIRS {
+            let next = point + dir;
+            if map.get(next.x, next.y) {
+                sets.union(index(point), index(next));
+            }
         }
-    }
+    };
 
-    fn set(&mut self, val: bool, Point { x, y }: Point) {
-        if x < N && y < N {
-            self.tiles[y][x] = val;
+    for point in (0..size * size).map(|i| Point::new(i % size, i / size)) {
+        if fallen[index(point)] == 0 {
+            free(&mut map, point, &mut sets);
         }
     }
-}
 
-fn parse<const N: usize, const M: usize>(
-    input: &'static str,
-) -> (Box<dyn Iterator<Item = Point>>, Map<N>) {
-    fn my_filter(line: &str) -> Option<Point> {
-        line.try_into().ok()
+    if sets.is_connected(index(start), index(end)) {
+        return None;
     }
 
-    let mut tiles_vec = Box::new(input.lines().filter_map(my_filter));
-
-    let mut map = Map::<N>::new([[true; N]; N]);
+    for block in blocks.iter().rev() {
+        fallen[index(*block)] -= 1;
+        if fallen[index(*block)] > 0 {
+            continue;
+        }
 
-    for point in (&mut tiles_vec).take(M) {
-        map.set(false, point);
+        free(&mut map, *block, &mut sets);
+        if sets.is_connected(index(start), index(end)) {
+            return Some(*block);
+        }
     }
 
-    (tiles_vec, map)
+    // Without any bytes the whole grid is free, so this isn't reached for a non-empty grid.
+    None
 }
 
-fn bfs<const N: usize>(map: &Map<N>) -> Option<usize> {
-    let mut queue = VecDeque::<(Point, usize)>::new();
-    let start = Point::new(0, 0);
-    let end = Point::new(N - 1, N - 1);
-    let mut seen = HashSet::from([start]);
+pub fn solve_2() -> String {
+    let size = config::param("d18", "size", SIZE);
+    let (blocks, _) = parse(
+        input::prepare(
+            "d18",
+            config::input("d18", INPUT, TEST),
+            &[Block::Lines("0-9,")],
+        ),
+        size,
+        0,
+    );
+    let blocks: Vec<Point> = blocks.collect();
+
+    first_blocking(&blocks, size).map_or_else(|| "none".to_string(), |block| block.to_string())
+}
 
-    queue.push_back((start, 0));
+#[test]
+fn test_first_blocking() {
+    let blocks = |input: &str| parse(input, 3, 0).0.collect::<Vec<_>>();
 
-    while let Some((pos, cost)) = queue.pop_front() {
-        if pos == end {
-            return Some(cost);
-        }
+    // The middle column is closed by the third byte, the repeated byte changes nothing.
+    let cut = blocks("1,0\n1,1\n1,2\n1,1\n");
+    assert_eq!(first_blocking(&cut, 3), Some(Point::new(1, 2)));
 
-        let next_cost = cost + 1;
+    let open = blocks("1,0\n1,1\n");
+    assert_eq!(first_blocking(&open, 3), None);
 
-        for dir in DIRS {
-            let next = pos + dir;
-            if map.get(next) && seen.insert(next) {
-                queue.push_back((next, next_cost));
-            }
+    let start_blocked = blocks("0,0\n");
+    assert_eq!(first_blocking(&start_blocked, 3), Some(Point::new(0, 0)));
+}
+
+// Expands the whole frontier by one step at a time, so the number of
+// steps taken when the end is reached is the length of the shortest path.
+#[cfg(test)]
+fn flood_fill(map: &BitGrid) -> Option<usize> {
+    let end = Point::new(map.width() - 1, map.height() - 1);
+    let mut seen = BitGrid::new(map.width(), map.height());
+    seen.set(0, 0);
+    let mut frontier = seen.clone();
+
+    for cost in 0.. {
+        if frontier.get(end.x, end.y) {
+            return Some(cost);
+        } else if frontier.is_empty() {
+            break;
         }
+
+        frontier = frontier.expanded();
+        frontier &= map;
+        frontier.and_not_assign(&seen);
+        seen |= &frontier;
     }
 
     None
 }
 
-pub fn solve_1() -> usize {
-    let (_, map) = parse::<SIZE, 1024>(INPUT);
-    bfs(&map).unwrap()
-}
-
-pub fn solve_2() -> String {
-    let (remaining_blocks, mut map) = parse::<SIZE, 1024>(INPUT);
+#[test]
+fn test_shortest_path() {
+    let mut numbers = crate::util::math::random_numbers(18);
 
-    for block in remaining_blocks {
-        map.set(false, block);
+    for fallen in [0, 200, 400, 600, 800] {
+        let input: String = (0..fallen)
+            .map(|_| {
+                let n = numbers.next().unwrap() as usize;
+                format!("{},{}\n", n % 21, n / 21 % 21)
+            })
+            .collect();
+        let (_, map) = parse(&input, 21, fallen);
 
-        if bfs(&map).is_none() {
-            return block.to_string();
-        }
+        assert_eq!(shortest_path(&map), flood_fill(&map));
     }
-
-    unreachable!();
 }
@@ -1,12 +1,24 @@
-use std::collections::{HashMap, HashSet};
+use crate::util::{
+    config,
+    hash::FastSet,
+    input::{self, Block},
+    memo::{memoize, memoize_dense},
+};
 
-#[allow(dead_code)]
 static TEST: &str = include_str!("../data/d19_test");
 static INPUT: &str = include_str!("../data/d19");
 
-fn parse(input: &str) -> (HashSet<&str>, Vec<&str>, usize) {
+fn input() -> &'static str {
+    input::prepare(
+        "d19",
+        config::input("d19", INPUT, TEST),
+        &[Block::Lines("wubrg, "), Block::Lines("wubrg")],
+    )
+}
+
+fn parse(input: &str) -> (FastSet<&str>, Vec<&str>, usize) {
     let mut lines = input.lines();
-    let mut towels = HashSet::new();
+    let mut towels = FastSet::default();
     let mut max_number_of_stripes = 0;
 
     if let Some(line) = lines.next() {
@@ -27,82 +39,74 @@ fn parse(input: &str) -> (HashSet<&str>, Vec<&str>, usize) {
     )
 }
 
-fn can_create_pattern(
-    towels: &HashSet<&'static str>,
-    pattern: &'static str,
-    max_number_of_stripes: usize,
-    seen: &mut HashSet<&'static str>,
-) -> bool {
-    if pattern.is_empty() {
-        return true;
-    }
-
-    for substring_len in 1..max_number_of_stripes.min(pattern.len()) + 1 {
-        if towels.contains(&pattern[..substring_len]) {
-            let next_pattern = &pattern[substring_len..];
-
-            if seen.insert(next_pattern)
-                && can_create_pattern(
-                    towels,
-                    &pattern[substring_len..],
-                    max_number_of_stripes,
-                    seen,
-                )
-            {
-                return true;
-            }
-        }
-    }
+fn can_create_pattern(towels: &FastSet<&str>, pattern: &str, max_number_of_stripes: usize) -> bool {
+    let mut can_create = memoize::<&str, bool, _>(|can_create, pattern| {
+        pattern.is_empty()
+            || (1..max_number_of_stripes.min(pattern.len()) + 1).any(|substring_len| {
+                towels.contains(&pattern[..substring_len]) && can_create(&pattern[substring_len..])
+            })
+    });
 
-    false
+    let can = can_create.call(pattern);
+    can_create.report("can_create_pattern");
+    can
 }
 
 fn count_number_of_possibilities(
-    towels: &HashSet<&'static str>,
-    pattern: &'static str,
+    towels: &FastSet<&str>,
+    pattern: &str,
     max_number_of_stripes: usize,
-    seen: &mut HashMap<&'static str, usize>,
 ) -> usize {
-    (1..max_number_of_stripes.min(pattern.len()) + 1)
-        .filter(|substring_len| towels.contains(&pattern[..*substring_len]))
-        .map(|substring_len| &pattern[substring_len..])
-        .map(|next_pattern| {
-            if next_pattern.is_empty() {
-                1
-            } else if !seen.contains_key(next_pattern) {
-                let number_of_possibilities = count_number_of_possibilities(
-                    towels,
-                    next_pattern,
-                    max_number_of_stripes,
-                    seen,
-                );
-                seen.insert(next_pattern, number_of_possibilities);
-                number_of_possibilities
-            } else {
-                *seen.get_mut(&next_pattern).unwrap()
-            }
-        })
-        .sum()
+    // Every suffix of the pattern is identified by its length.
+    let mut count = memoize_dense::<usize, _>(pattern.len() + 1, |count, len| {
+        let suffix = &pattern[pattern.len() - len..];
+        if suffix.is_empty() {
+            1
+        } else {
+            (1..max_number_of_stripes.min(len) + 1)
+                .filter(|substring_len| towels.contains(&suffix[..*substring_len]))
+                .map(|substring_len| count(len - substring_len))
+                .sum()
+        }
+    });
+
+    let possibilities = count.call(pattern.len());
+    count.report("count_number_of_possibilities");
+    possibilities
 }
 
 pub fn solve_1() -> usize {
-    let (towels, patterns, max_number_of_stripes) = parse(INPUT);
+    let (towels, patterns, max_number_of_stripes) = parse(input());
 
     patterns
         .iter()
-        .filter(|pattern| {
-            can_create_pattern(&towels, pattern, max_number_of_stripes, &mut HashSet::new())
-        })
+        .filter(|pattern| can_create_pattern(&towels, pattern, max_number_of_stripes))
         .count()
 }
 
 pub fn solve_2() -> usize {
-    let (towels, patterns, max_number_of_stripes) = parse(INPUT);
+    let (towels, patterns, max_number_of_stripes) = parse(input());
 
     patterns
         .iter()
-        .map(|p| {
-            count_number_of_possibilities(&towels, p, max_number_of_stripes, &mut HashMap::new())
-        })
+        .map(|p| count_number_of_possibilities(&towels, p, max_number_of_stripes))
         .sum()
 }
+
+#[test]
+fn test_long_pattern() {
+    let (towels, _, max_number_of_stripes) = parse("r, wu, rwu\n\n");
+
+    // Every `rwu` can be made as `r` + `wu` or as `rwu`, a run of `r` only one way.
+    let pattern = "rwu".repeat(20) + &"r".repeat(240);
+    assert!(can_create_pattern(&towels, &pattern, max_number_of_stripes));
+    assert_eq!(
+        count_number_of_possibilities(&towels, &pattern, max_number_of_stripes),
+        1 << 20
+    );
+    assert!(!can_create_pattern(
+        &towels,
+        &(pattern.clone() + "w"),
+        max_number_of_stripes
+    ));
+}
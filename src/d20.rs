@@ -1,100 +1,15 @@
-use std::fmt::Display;
+use crate::util::{
+    bitgrid::BitGrid,
+    config,
+    input::{self, Block},
+    point::{Point, DIRS},
+    search::{self, SearchProblem},
+    span::span,
+};
 
-use crate::sizes;
-
-use crate::util::point::{Point, DIRS};
-
-#[allow(dead_code)]
 static TEST: &str = include_str!("../data/d20_test");
 static INPUT: &str = include_str!("../data/d20");
 
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-enum Tile {
-    Start,
-    End,
-    Floor,
-    #[default]
-    Wall,
-}
-
-impl From<char> for Tile {
-    fn from(value: char) -> Self {
-        match value {
-            'S' => Self::Start,
-            'E' => Self::End,
-            '.' => Self::Floor,
-            _ => Self::Wall,
-        }
-    }
-}
-
-struct BitMapIdx {
-    array_index: usize,
-    indexed_bit_in_array_index: u8,
-}
-
-/// S has to be a power of 2
-#[derive(Debug)]
-struct BitMap<const S: usize> {
-    tiles: [u8; S],
-}
-
-impl<const S: usize> BitMap<S> {
-    const DIM: usize = (S * 8) >> ((S * 8).trailing_zeros() / 2);
-}
-
-impl<const S: usize> BitMap<S> {
-    fn new() -> Self {
-        Self { tiles: [0; S] }
-    }
-
-    fn is_in_bounds(&self, x: usize, y: usize) -> bool {
-        x < Self::DIM && y < Self::DIM
-    }
-
-    fn get_index(&self, x: usize, y: usize) -> BitMapIdx {
-        let indexed_bit = y * Self::DIM + x;
-        let array_index = indexed_bit / 8;
-        let indexed_bit_in_array_index = 1 << (7 - (indexed_bit % 8));
-        BitMapIdx {
-            array_index,
-            indexed_bit_in_array_index,
-        }
-    }
-
-    fn get(&self, x: usize, y: usize) -> bool {
-        if self.is_in_bounds(x, y) {
-            let idx = self.get_index(x, y);
-            self.tiles[idx.array_index] & idx.indexed_bit_in_array_index != 0
-        } else {
-            false
-        }
-    }
-
-    fn set(&mut self, x: usize, y: usize) {
-        if self.is_in_bounds(x, y) {
-            let idx = self.get_index(x, y);
-            self.tiles[idx.array_index] |= idx.indexed_bit_in_array_index
-        }
-    }
-}
-
-impl<const D: usize> Display for BitMap<D> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let width = D / Self::DIM;
-        let height = D / width;
-        let mut s = String::new();
-        for y in 0..height {
-            for x in 0..width {
-                s.push_str(&format!("{:08b}", self.tiles[y * width + x]));
-            }
-            s.push('\n');
-        }
-
-        write!(f, "{}", s)
-    }
-}
-
 struct RhombusIterator {
     center: Point,
     current: Point,
@@ -126,7 +41,7 @@ impl Iterator for RhombusIterator {
             None
         } else {
             let next = self.current;
-            self.current = self.current + Self::DIRS[self.i];
+            self.current += Self::DIRS[self.i];
             if self.current.x == self.center.x || self.current.y == self.center.y {
                 self.i += 1;
             }
@@ -135,8 +50,11 @@ impl Iterator for RhombusIterator {
     }
 }
 
-fn parse_bit_map<const D: usize>(input: &str) -> (BitMap<D>, Point, Point) {
-    let mut bit_map = BitMap::new();
+fn parse_bit_map(input: &str) -> (BitGrid, Point, Point) {
+    let _s = span("parse_bit_map");
+    let width = input.lines().next().map(|line| line.len()).unwrap_or(0);
+    let height = input.lines().count();
+    let mut bit_map = BitGrid::new(width, height);
     let mut start = Point::new(0, 0);
     let mut end = Point::new(0, 0);
 
@@ -159,24 +77,44 @@ fn parse_bit_map<const D: usize>(input: &str) -> (BitMap<D>, Point, Point) {
     (bit_map, start, end)
 }
 
-fn dfs<const BM_ARRAY_SIZE: usize, const AREA: usize>(
-    map: &BitMap<BM_ARRAY_SIZE>,
+struct Track<'a> {
+    map: &'a BitGrid,
     start: Point,
-) -> [Option<u16>; AREA] {
-    let mut distances = [None; AREA];
-    let mut current_opt = Some((start, 0));
-
-    while let Some((current, cost)) = current_opt.take() {
-        distances[current.flatten(BitMap::<BM_ARRAY_SIZE>::DIM)] = Some(cost);
-
-        for dir in DIRS {
-            let next = current + dir;
-            if map.get(next.x, next.y)
-                && distances[next.flatten(BitMap::<BM_ARRAY_SIZE>::DIM)].is_none()
-            {
-                current_opt = Some((next, cost + 1));
-            }
-        }
+    end: Point,
+}
+
+impl SearchProblem for Track<'_> {
+    type State = Point;
+
+    fn start(&self) -> Vec<Point> {
+        vec![self.start]
+    }
+
+    fn successors(&self, current: &Point, next: &mut Vec<(Point, usize)>) {
+        next.extend(
+            DIRS.into_iter()
+                .map(|dir| *current + dir)
+                .filter(|next| self.map.get(next.x, next.y))
+                .map(|next| (next, 1)),
+        );
+    }
+
+    fn is_goal(&self, point: &Point) -> bool {
+        *point == self.end
+    }
+}
+
+// The race track is a single path, so the shortest path to the end visits every tile.
+fn distances(map: &BitGrid, start: Point, end: Point) -> Vec<Option<u16>> {
+    let _s = span("distances");
+    let width = map.width();
+    let mut distances = vec![None; width * map.height()];
+    let result = search::bfs(&Track { map, start, end });
+    result.stats.report("distances");
+
+    let solution = result.solution.expect("End should be reachable");
+    for (cost, point) in solution.path.into_iter().enumerate() {
+        distances[point.flatten(width)] = Some(cost as u16);
     }
 
     distances
@@ -185,10 +123,12 @@ fn dfs<const BM_ARRAY_SIZE: usize, const AREA: usize>(
 fn cheat(
     distances: &[Option<u16>],
     max_cheat_length: u16,
-    map_size: usize,
+    width: usize,
+    height: usize,
     path_length: u16,
     min_length_to_be_saved: u16,
 ) -> usize {
+    let _s = span("cheat");
     let filter_costs = |(i, cost_opt): (usize, &Option<u16>)| match *cost_opt {
         Some(cost) => Some((i, cost)),
         _none => None,
@@ -200,14 +140,17 @@ fn cheat(
         .iter()
         .enumerate()
         .filter_map(filter_costs)
-        .filter(|(_, cost)| *cost < path_length - min_length_to_be_saved)
+        // Widened, so neither side can overflow and the costs never have to be subtracted.
+        .filter(|(_, cost)| {
+            u32::from(*cost) + u32::from(min_length_to_be_saved) < u32::from(path_length)
+        })
     {
-        let start = Point::unflatten(start_flat, map_size);
+        let start = Point::unflatten(start_flat, width);
 
         for cheat_length in 2..=max_cheat_length {
             number_of_cheats_saving_time += RhombusIterator::new(start, cheat_length as usize)
-                .filter(|point| point.x < map_size && point.y < map_size)
-                .filter_map(|point| distances[point.flatten(map_size)])
+                .filter(|point| point.x < width && point.y < height)
+                .filter_map(|point| distances[point.flatten(width)])
                 .filter(|cost_from_cheat_end| *cost_from_cheat_end > cost_from_start + cheat_length)
                 .filter(|cost_from_cheat_end| {
                     *cost_from_cheat_end - cost_from_start - cheat_length >= min_length_to_be_saved
@@ -220,36 +163,35 @@ fn cheat(
 }
 
 fn solve(max_cheat_length: u16, min_saved_cost: u16) -> usize {
-    sizes!(256, 256);
-    let (m, start, end) = parse_bit_map::<SIZE_ARRAY>(INPUT);
+    let (m, start, end) = parse_bit_map(input::prepare(
+        "d20",
+        config::input("d20", INPUT, TEST),
+        &[Block::Grid("#.SE")],
+    ));
 
-    let distances = dfs::<SIZE_ARRAY, AREA>(&m, start);
-    let total_cost = distances[end.flatten(WIDTH)].unwrap();
+    let distances = distances(&m, start, end);
+    let total_cost = distances[end.flatten(m.width())].unwrap();
 
     cheat(
         &distances,
         max_cheat_length,
-        WIDTH,
+        m.width(),
+        m.height(),
         total_cost,
         min_saved_cost,
     )
 }
 
 pub fn solve_1() -> usize {
-    solve(2, 100)
+    solve(
+        config::param("d20", "max_cheat_1", 2),
+        config::param("d20", "min_saved_1", 100),
+    )
 }
 
 pub fn solve_2() -> usize {
-    solve(20, 100)
-}
-
-#[macro_export]
-macro_rules! sizes {
-    ($width:expr, $height:expr) => {
-        const WIDTH: usize = $width;
-        const HEIGHT: usize = $height;
-        const AREA: usize = WIDTH * HEIGHT;
-        assert_eq!(AREA % 8, 0);
-        const SIZE_ARRAY: usize = AREA / 8;
-    };
+    solve(
+        config::param("d20", "max_cheat_2", 20),
+        config::param("d20", "min_saved_2", 100),
+    )
 }
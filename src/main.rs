@@ -1,5 +1,12 @@
 use std::time::Instant;
 
+use util::{
+    answer::{self, Answer},
+    checked,
+    config::{self, Config},
+    hash, span, trace,
+};
+
 mod d01;
 mod d02;
 mod d03;
@@ -23,59 +30,172 @@ mod d20;
 
 mod util;
 
+type Solver = fn() -> Answer;
+
+const SOLUTIONS: [(u8, u8, Solver); 40] = [
+    (1, 1, || d01::get_solution_1().into()),
+    (1, 2, || d01::get_solution_2().into()),
+    (2, 1, || d02::get_solution_1().into()),
+    (2, 2, || d02::get_solution_2().into()),
+    (3, 1, || d03::get_solution_1().into()),
+    (3, 2, || d03::get_solution_2().into()),
+    (4, 1, || d04::get_solution_1().into()),
+    (4, 2, || d04::get_solution_2().into()),
+    (5, 1, || d05::get_solution_1().into()),
+    (5, 2, || d05::get_solution_2().into()),
+    (6, 1, || d06::solve_1().into()),
+    (6, 2, || d06::solve_2().into()),
+    (7, 1, || d07::solve_1().into()),
+    (7, 2, || d07::solve_2().into()),
+    (8, 1, || d08::solve_1().into()),
+    (8, 2, || d08::solve_2().into()),
+    (9, 1, || d09::solve_1().into()),
+    (9, 2, || d09::solve_2().into()),
+    (10, 1, || d10::solve_1().into()),
+    (10, 2, || d10::solve_2().into()),
+    (11, 1, || d11::solve_1().into()),
+    (11, 2, || d11::solve_2().into()),
+    (12, 1, || d12::solve_1().into()),
+    (12, 2, || d12::solve_2().into()),
+    (13, 1, || d13::solve_1().into()),
+    (13, 2, || d13::solve_2().into()),
+    (14, 1, || d14::solve_1().into()),
+    (14, 2, || d14::solve_2().into()),
+    (15, 1, || d15::solve_1().into()),
+    (15, 2, || d15::solve_2().into()),
+    (16, 1, || d16::solve_1().into()),
+    (16, 2, || d16::solve_2().into()),
+    (17, 1, || d17::solve_1().into()),
+    (17, 2, || d17::solve_2().into()),
+    (18, 1, || d18::solve_1().into()),
+    (18, 2, || d18::solve_2().into()),
+    (19, 1, || d19::solve_1().into()),
+    (19, 2, || d19::solve_2().into()),
+    (20, 1, || d20::solve_1().into()),
+    (20, 2, || d20::solve_2().into()),
+];
+
 fn main() {
-    aoc_result!(1, 1, d01::get_solution_1());
-    aoc_result!(1, 2, d01::get_solution_2());
-    aoc_result!(2, 1, d02::get_solution_1());
-    aoc_result!(2, 2, d02::get_solution_2());
-    aoc_result!(3, 1, d03::get_solution_1());
-    aoc_result!(3, 2, d03::get_solution_2());
-    aoc_result!(4, 1, d04::get_solution_1());
-    aoc_result!(4, 2, d04::get_solution_2());
-    aoc_result!(5, 1, d05::get_solution_1());
-    aoc_result!(5, 2, d05::get_solution_2());
-    aoc_result!(6, 1, d06::solve_1());
-    aoc_result!(6, 2, d06::solve_2());
-    aoc_result!(7, 1, d07::solve_1());
-    aoc_result!(7, 2, d07::solve_2());
-    aoc_result!(8, 1, d08::solve_1());
-    aoc_result!(8, 2, d08::solve_2());
-    aoc_result!(9, 1, d09::solve_1());
-    aoc_result!(9, 2, d09::solve_2());
-    aoc_result!(10, 1, d10::solve_1());
-    aoc_result!(10, 2, d10::solve_2());
-    aoc_result!(11, 1, d11::solve_1());
-    aoc_result!(11, 2, d11::solve_2());
-    aoc_result!(12, 1, d12::solve_1());
-    aoc_result!(12, 2, d12::solve_2());
-    aoc_result!(13, 1, d13::solve_1());
-    aoc_result!(13, 2, d13::solve_2());
-    aoc_result!(14, 1, d14::solve_1());
-    aoc_result!(14, 2, d14::solve_2());
-    aoc_result!(15, 1, d15::solve_1());
-    aoc_result!(15, 2, d15::solve_2());
-    aoc_result!(16, 1, d16::solve_1());
-    aoc_result!(16, 2, d16::solve_2());
-    aoc_result!(17, 1, d17::solve_1());
-    aoc_result!(17, 2, d17::solve_2());
-    aoc_result!(18, 1, d18::solve_1());
-    aoc_result!(18, 2, d18::solve_2());
-    aoc_result!(19, 1, d19::solve_1());
-    aoc_result!(19, 2, d19::solve_2());
-    aoc_result!(20, 1, d20::solve_1());
-    aoc_result!(20, 2, d20::solve_2());
-}
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    config::init(load_config(&args));
+
+    if args.iter().any(|arg| arg == "dot") {
+        print!("{}", d16::to_dot());
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "route") {
+        print!("{}", d06::to_route_map());
+        return;
+    }
+
+    if args.iter().any(|arg| arg == "rules") {
+        print!("{}", d05::analyze());
+        return;
+    }
+
+    let json = args.iter().any(|arg| arg == "--json");
+    let explain = args.iter().any(|arg| arg == "--explain");
+    let only_day = args
+        .iter()
+        .position(|arg| arg == "--day")
+        .map(|i| args.get(i + 1).and_then(|day| day.parse::<u8>().ok()))
+        .map(|day| day.expect("--day has to be followed by a day number"));
+
+    if explain {
+        trace::enable();
+    }
+
+    let start = Instant::now();
+    let mut parts = Vec::new();
+
+    for (day, part, solve) in SOLUTIONS {
+        if only_day.is_some_and(|only_day| only_day != day) {
+            continue;
+        }
 
-#[macro_export]
-macro_rules! aoc_result {
-    ( $d:literal, $p:literal, $r:expr ) => {
         let now = Instant::now();
+        let result = checked::run(solve);
+        let micros = now.elapsed().as_micros();
+        let phases = span::take();
+        let events = trace::take();
+
+        if json {
+            let result = match result {
+                Ok(answer) => format!(r#""answer":{}"#, answer.to_json()),
+                Err(overflow) => format!(
+                    r#""overflow":{}"#,
+                    answer::json_string(&overflow.to_string())
+                ),
+            };
+            let events: Vec<_> = events.iter().map(trace::Record::to_json).collect();
+            parts.push(format!(
+                r#"{{"day":{day},"part":{part},{result},"micros":{micros},"phases":{},"events":[{}]}}"#,
+                span::phases_to_json(&phases),
+                events.join(",")
+            ));
+            continue;
+        }
+
+        match result {
+            Ok(answer) => println!("d{day:2}.{part}: {answer:16}\t{micros:10}us"),
+            Err(overflow) => println!("d{day:2}.{part}: {overflow}"),
+        }
+
+        let mut table = String::new();
+        span::write_table(&mut table, &phases, 0).unwrap();
+        print!("{table}");
+
+        for event in events {
+            println!("        {event}");
+        }
+    }
+
+    if json {
+        println!(
+            r#"{{"hasher":"{}","micros":{},"parts":[{}]}}"#,
+            hash::HASHER_NAME,
+            start.elapsed().as_micros(),
+            parts.join(",")
+        );
+    } else {
         println!(
-            "d{:2}.{}: {:16}\t{:10}us",
-            $d,
-            $p,
-            $r,
-            now.elapsed().as_micros()
+            "total: {:>24}us ({})",
+            start.elapsed().as_micros(),
+            hash::HASHER_NAME
         );
+    }
+}
+
+// Reads the file given with `--config` (or `aoc.toml` if it exists) and applies
+// all `--set day.key=value` overrides.
+fn load_config(args: &[String]) -> Config {
+    let path = args.iter().position(|arg| arg == "--config").map(|i| {
+        args.get(i + 1)
+            .expect("--config has to be followed by a path")
+    });
+
+    let mut config = match path {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .unwrap_or_else(|e| panic!("Can't read config {path}: {e}"));
+            Config::parse(&text).unwrap_or_else(|e| panic!("Invalid config at {path}:{e}"))
+        }
+        None => std::fs::read_to_string("aoc.toml")
+            .map(|text| {
+                Config::parse(&text).unwrap_or_else(|e| panic!("Invalid config at aoc.toml:{e}"))
+            })
+            .unwrap_or_default(),
     };
+
+    for (i, _) in args.iter().enumerate().filter(|(_, arg)| *arg == "--set") {
+        let assignment = args
+            .get(i + 1)
+            .expect("--set has to be followed by day.key=value");
+        config
+            .set(assignment)
+            .unwrap_or_else(|e| panic!("Invalid --set: {e}"));
+    }
+
+    config
 }
@@ -0,0 +1,140 @@
+use std::{convert::Infallible, fmt::Display, str::FromStr};
+
+/// The result of a puzzle part, whatever type the solver returns.
+///
+/// Answers compare equal if they would be accepted as the same answer, so `Signed(5)`
+/// equals `Unsigned(5)` and `Text("4, 3,2")` equals `Text("4,3,2")`. Other whitespace
+/// inside text is significant.
+#[derive(Debug, Clone)]
+pub enum Answer {
+    Unsigned(u64),
+    Signed(i64),
+    Text(String),
+}
+
+impl Answer {
+    // Numbers in their shortest form and text without whitespace around its ends and
+    // list separators.
+    fn canonical(&self) -> String {
+        match self {
+            Answer::Unsigned(n) => n.to_string(),
+            Answer::Signed(n) => n.to_string(),
+            Answer::Text(text) => match text.parse::<Answer>() {
+                Ok(Answer::Text(text)) => {
+                    text.split(',').map(str::trim).collect::<Vec<_>>().join(",")
+                }
+                Ok(number) => number.canonical(),
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        match self {
+            Answer::Unsigned(n) => n.to_string(),
+            Answer::Signed(n) => n.to_string(),
+            Answer::Text(text) => json_string(text),
+        }
+    }
+}
+
+/// Quotes and escapes `text` as a JSON string.
+pub fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+// Padding works like for the underlying value, so numbers are right aligned
+// and text is left aligned.
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Unsigned(n) => n.fmt(f),
+            Answer::Signed(n) => n.fmt(f),
+            Answer::Text(text) => f.pad(text),
+        }
+    }
+}
+
+/// Numbers become `Unsigned` or `Signed`, anything else is kept as `Text`.
+impl FromStr for Answer {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        Ok(if let Ok(n) = s.parse() {
+            Answer::Unsigned(n)
+        } else if let Ok(n) = s.parse() {
+            Answer::Signed(n)
+        } else {
+            Answer::Text(s.to_string())
+        })
+    }
+}
+
+impl PartialEq for Answer {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+impl Eq for Answer {}
+
+macro_rules! impl_from {
+    ( $variant:ident as $target:ty: $( $t:ty ),* ) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(value: $t) -> Self {
+                    Answer::$variant(value as $target)
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(Unsigned as u64: u8, u16, u32, u64, usize);
+impl_from!(Signed as i64: i8, i16, i32, i64, isize);
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Answer::Text(value)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(value: &str) -> Self {
+        Answer::Text(value.to_string())
+    }
+}
+
+#[test]
+fn test_answer_equality() {
+    assert_eq!(Answer::from(5isize), Answer::from(5usize));
+    assert_eq!("4, 3,2".parse::<Answer>(), Ok(Answer::from("4,3,2")));
+    assert_eq!(Answer::from(" 0042 "), Answer::from(42u32));
+    assert_eq!("-7".parse::<Answer>(), Ok(Answer::Signed(-7)));
+    assert_ne!(Answer::from(-7i64), Answer::from(7u64));
+    assert_ne!(Answer::from("69,69"), Answer::from("6,969"));
+    assert_ne!(Answer::from("4 3 2"), Answer::from(432u32));
+    assert_ne!(Answer::from("a b"), Answer::from("ab"));
+    assert_eq!(Answer::from(" a b ,c"), Answer::from("a b,c"));
+
+    assert_eq!(format!("[{:4}]", Answer::from(12u8)), "[  12]");
+    assert_eq!(format!("[{:4}]", Answer::from("ab")), "[ab  ]");
+    assert_eq!(Answer::from("a\"b").to_json(), r#""a\"b""#);
+}
@@ -0,0 +1,295 @@
+use std::{
+    fmt::Display,
+    ops::{BitAndAssign, BitOrAssign, BitXorAssign},
+};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A grid of bits with arbitrary width and height.
+///
+/// Every row starts at a new `u64` word, so whole rows can be combined word by word.
+/// Bit `x % 64` of word `x / 64` of a row stores the tile at column `x`. Bits past the
+/// width of a row are always kept at 0.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    words: Vec<u64>,
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+
+        Self {
+            words: vec![0; words_per_row * height],
+            width,
+            height,
+            words_per_row,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    fn index(&self, x: usize, y: usize) -> (usize, u64) {
+        (y * self.words_per_row + x / WORD_BITS, 1 << (x % WORD_BITS))
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        if self.is_in_bounds(x, y) {
+            let (word, mask) = self.index(x, y);
+            self.words[word] & mask != 0
+        } else {
+            false
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize) {
+        if self.is_in_bounds(x, y) {
+            let (word, mask) = self.index(x, y);
+            self.words[word] |= mask;
+        }
+    }
+
+    pub fn unset(&mut self, x: usize, y: usize) {
+        if self.is_in_bounds(x, y) {
+            let (word, mask) = self.index(x, y);
+            self.words[word] &= !mask;
+        }
+    }
+
+    fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// Mask of the valid bits in the last word of each row.
+    fn tail_mask(&self) -> u64 {
+        match self.width % WORD_BITS {
+            0 => u64::MAX,
+            n => (1 << n) - 1,
+        }
+    }
+
+    pub fn fill(&mut self) {
+        let tail_mask = self.tail_mask();
+        for y in 0..self.height {
+            let row = self.row_mut(y);
+            row.fill(u64::MAX);
+            if let Some(last) = row.last_mut() {
+                *last &= tail_mask;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Unsets every bit that is set in `other`.
+    #[cfg(test)]
+    pub fn and_not_assign(&mut self, other: &Self) {
+        self.assert_same_size(other);
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= !b;
+        }
+    }
+
+    /// Moves every bit one column towards `x == 0`. Bits in the first column are dropped.
+    #[cfg(test)]
+    pub fn shifted_left(&self) -> Self {
+        let mut shifted = self.clone();
+        for y in 0..self.height {
+            shift_row_down(shifted.row_mut(y));
+        }
+
+        shifted
+    }
+
+    /// Moves every bit one column away from `x == 0`. Bits in the last column are dropped.
+    #[cfg(test)]
+    pub fn shifted_right(&self) -> Self {
+        let tail_mask = self.tail_mask();
+        let mut shifted = self.clone();
+        for y in 0..self.height {
+            let row = shifted.row_mut(y);
+            shift_row_up(row);
+            if let Some(last) = row.last_mut() {
+                *last &= tail_mask;
+            }
+        }
+
+        shifted
+    }
+
+    /// Moves every bit one row towards `y == 0`. Bits in the first row are dropped.
+    #[cfg(test)]
+    pub fn shifted_up(&self) -> Self {
+        let mut shifted = Self::new(self.width, self.height);
+        let offset = self.words_per_row;
+        if self.height > 0 {
+            shifted.words[..self.words.len() - offset].copy_from_slice(&self.words[offset..]);
+        }
+
+        shifted
+    }
+
+    /// Moves every bit one row away from `y == 0`. Bits in the last row are dropped.
+    #[cfg(test)]
+    pub fn shifted_down(&self) -> Self {
+        let mut shifted = Self::new(self.width, self.height);
+        let offset = self.words_per_row;
+        if self.height > 0 {
+            shifted.words[offset..].copy_from_slice(&self.words[..self.words.len() - offset]);
+        }
+
+        shifted
+    }
+
+    /// Returns the set bits together with their four direct neighbours.
+    ///
+    /// Combined with a mask of open tiles this advances a whole BFS frontier at once.
+    #[cfg(test)]
+    pub fn expanded(&self) -> Self {
+        let mut expanded = self.clone();
+        expanded |= &self.shifted_left();
+        expanded |= &self.shifted_right();
+        expanded |= &self.shifted_up();
+        expanded |= &self.shifted_down();
+
+        expanded
+    }
+
+    /// Iterates over the coordinates of all set bits, row by row.
+    pub fn iter_ones(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.words.iter().enumerate().flat_map(move |(i, word)| {
+            let y = i / self.words_per_row;
+            let x_offset = (i % self.words_per_row) * WORD_BITS;
+            let mut word = *word;
+
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    None
+                } else {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    Some((x_offset + bit, y))
+                }
+            })
+        })
+    }
+
+    fn assert_same_size(&self, other: &Self) {
+        assert_eq!(
+            (self.width, self.height),
+            (other.width, other.height),
+            "BitGrids have different sizes"
+        );
+    }
+}
+
+// Shifts the bits of a row towards index 0, carrying over word boundaries.
+#[cfg(test)]
+fn shift_row_down(row: &mut [u64]) {
+    for i in 0..row.len() {
+        let carry = row
+            .get(i + 1)
+            .map(|next| next << (WORD_BITS - 1))
+            .unwrap_or(0);
+        row[i] = (row[i] >> 1) | carry;
+    }
+}
+
+// Shifts the bits of a row away from index 0, carrying over word boundaries.
+#[cfg(test)]
+fn shift_row_up(row: &mut [u64]) {
+    for i in (0..row.len()).rev() {
+        let carry = if i > 0 {
+            row[i - 1] >> (WORD_BITS - 1)
+        } else {
+            0
+        };
+        row[i] = (row[i] << 1) | carry;
+    }
+}
+
+impl BitAndAssign<&BitGrid> for BitGrid {
+    fn bitand_assign(&mut self, rhs: &BitGrid) {
+        self.assert_same_size(rhs);
+        for (a, b) in self.words.iter_mut().zip(&rhs.words) {
+            *a &= b;
+        }
+    }
+}
+
+impl BitOrAssign<&BitGrid> for BitGrid {
+    fn bitor_assign(&mut self, rhs: &BitGrid) {
+        self.assert_same_size(rhs);
+        for (a, b) in self.words.iter_mut().zip(&rhs.words) {
+            *a |= b;
+        }
+    }
+}
+
+impl BitXorAssign<&BitGrid> for BitGrid {
+    fn bitxor_assign(&mut self, rhs: &BitGrid) {
+        self.assert_same_size(rhs);
+        for (a, b) in self.words.iter_mut().zip(&rhs.words) {
+            *a ^= b;
+        }
+    }
+}
+
+impl Display for BitGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.height {
+            let row: String = (0..self.width)
+                .map(|x| if self.get(x, y) { '#' } else { '.' })
+                .collect();
+            writeln!(f, "{row}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_shift_across_words() {
+    let mut grid = BitGrid::new(130, 3);
+    grid.set(63, 1);
+    grid.set(129, 1);
+
+    let right = grid.shifted_right();
+    assert_eq!(right.iter_ones().collect::<Vec<_>>(), vec![(64, 1)]);
+
+    let left = grid.shifted_left();
+    assert_eq!(
+        left.iter_ones().collect::<Vec<_>>(),
+        vec![(62, 1), (128, 1)]
+    );
+
+    assert_eq!(grid.expanded().count_ones(), 9);
+    assert_eq!(grid.shifted_up().shifted_up().count_ones(), 0);
+}
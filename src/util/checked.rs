@@ -0,0 +1,143 @@
+//! Arithmetic which reports overflows when the `checked` feature is enabled.
+//!
+//! Without the feature these are the plain operators. With it, an overflow unwinds with
+//! an [`Overflow`] pointing to the caller, which [`run`] turns into an error, so the
+//! runner can report it for the day and part instead of printing a wrong answer.
+
+use std::{
+    fmt::Display,
+    ops::{Add, Mul, Sub},
+    panic::Location,
+};
+
+// Only the checked build calls these, the plain one uses the operators.
+#[cfg_attr(not(feature = "checked"), allow(dead_code))]
+pub trait Int:
+    Copy + Display + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_int {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl Int for $t {
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Where and in which operation an overflow happened.
+#[derive(Debug, Clone)]
+pub struct Overflow {
+    pub operation: String,
+    pub location: &'static Location<'static>,
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` overflowed at {}", self.operation, self.location)
+    }
+}
+
+#[cfg(feature = "checked")]
+#[track_caller]
+fn check<T: Int>(result: Option<T>, a: T, operator: &str, b: T) -> T {
+    match result {
+        Some(result) => result,
+        None => std::panic::panic_any(Overflow {
+            operation: format!("{a} {operator} {b}"),
+            location: Location::caller(),
+        }),
+    }
+}
+
+#[track_caller]
+pub fn add<T: Int>(a: T, b: T) -> T {
+    #[cfg(feature = "checked")]
+    return check(a.checked_add(b), a, "+", b);
+    #[cfg(not(feature = "checked"))]
+    return a + b;
+}
+
+#[track_caller]
+pub fn sub<T: Int>(a: T, b: T) -> T {
+    #[cfg(feature = "checked")]
+    return check(a.checked_sub(b), a, "-", b);
+    #[cfg(not(feature = "checked"))]
+    return a - b;
+}
+
+#[track_caller]
+pub fn mul<T: Int>(a: T, b: T) -> T {
+    #[cfg(feature = "checked")]
+    return check(a.checked_mul(b), a, "*", b);
+    #[cfg(not(feature = "checked"))]
+    return a * b;
+}
+
+/// Sums up all values with [`add`].
+#[track_caller]
+pub fn sum<T: Int + Default>(values: impl IntoIterator<Item = T>) -> T {
+    let mut sum = T::default();
+
+    for value in values {
+        sum = add(sum, value);
+    }
+
+    sum
+}
+
+/// Runs a solver, returning the overflow instead of unwinding if one happened.
+/// Any other panic is passed on.
+pub fn run<T>(solve: impl FnOnce() -> T) -> Result<T, Overflow> {
+    #[cfg(feature = "checked")]
+    {
+        static HOOK: std::sync::Once = std::sync::Once::new();
+
+        // overflows are reported by the runner, so don't let the default hook print them
+        HOOK.call_once(|| {
+            let default_hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                if !info.payload().is::<Overflow>() {
+                    default_hook(info);
+                }
+            }));
+        });
+
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(solve)).map_err(|payload| {
+            match payload.downcast::<Overflow>() {
+                Ok(overflow) => *overflow,
+                Err(payload) => std::panic::resume_unwind(payload),
+            }
+        })
+    }
+    #[cfg(not(feature = "checked"))]
+    Ok(solve())
+}
+
+#[cfg(feature = "checked")]
+#[test]
+fn test_overflow_is_located() {
+    let overflow = run(|| mul(u64::MAX, 2)).unwrap_err();
+    assert_eq!(overflow.operation, format!("{} * 2", u64::MAX));
+    assert_eq!(overflow.location.file(), file!());
+
+    assert_eq!(run(|| sum([1u8, 2, 3])).unwrap(), 6);
+    assert!(run(|| sum([200u8, 100])).is_err());
+}
@@ -0,0 +1,253 @@
+//! Puzzle parameters which differ between the example and the real input,
+//! e.g. the size of a grid.
+//!
+//! They are read from a TOML-like file with one section per day:
+//!
+//! ```toml
+//! [d18]
+//! input = "test"  # use the example input
+//! size = 7
+//! fallen = 12
+//! ```
+//!
+//! and can be overridden with `--set d18.size=7`. Parameters which are not set
+//! keep the default given by the day.
+
+use std::{str::FromStr, sync::OnceLock};
+
+use super::{hash::FastMap, parse::ParseError};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Debug, Default, Clone)]
+pub struct Config {
+    values: FastMap<(String, String), String>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut config = Config::default();
+        let mut section = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+
+            if line.is_empty() {
+                continue;
+            } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = Some(name.trim().to_string());
+            } else if let Some((key, value)) = line.split_once('=') {
+                let section = section.clone().ok_or_else(|| {
+                    ParseError::new(i + 1, 1, "expected a `[day]` section before the first key")
+                })?;
+                config.insert(section, key, value);
+            } else {
+                return Err(ParseError::new(
+                    i + 1,
+                    1,
+                    "expected `[day]` or `key = value`",
+                ));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Applies an override like `d18.size=7`.
+    pub fn set(&mut self, assignment: &str) -> Result<(), String> {
+        let (path, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("expected `day.key=value`, got `{assignment}`"))?;
+        let (section, key) = path
+            .split_once('.')
+            .ok_or_else(|| format!("expected `day.key` in `{assignment}`"))?;
+
+        self.insert(section.trim().to_string(), key, value);
+        Ok(())
+    }
+
+    fn insert(&mut self, section: String, key: &str, value: &str) {
+        let value = value.trim();
+        let value = match unquote(value) {
+            Some(Ok(text)) => text,
+            _ => value.to_string(),
+        };
+
+        self.values.insert((section, key.trim().to_string()), value);
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.values
+            .get(&(section.to_string(), key.to_string()))
+            .map(String::as_str)
+    }
+}
+
+// Cuts off a `#` comment, unless the `#` is inside a quoted value.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut chars = line.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quoted => {
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => (),
+        }
+    }
+
+    line
+}
+
+// The text of a string like `"a \"b\""`, `None` if `value` is not one quoted string.
+// Inside the quotes `\"` is a quote and `\\` a backslash.
+fn unquote(value: &str) -> Option<Result<String, String>> {
+    let mut chars = value.strip_prefix('"')?.chars();
+    let mut text = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return chars.as_str().is_empty().then_some(Ok(text)),
+            '\\' => match chars.next() {
+                Some(c @ ('"' | '\\')) => text.push(c),
+                Some(c) => return Some(Err(format!("unknown escape `\\{c}`"))),
+                None => break,
+            },
+            c => text.push(c),
+        }
+    }
+
+    Some(Err(format!("missing closing quote in `{value}`")))
+}
+
+// Splits a list at the commas outside of quotes and unquotes the items.
+fn list_items(value: &str) -> Result<Vec<String>, String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut chars = value.char_indices();
+
+    let mut push = |item: &str| {
+        let item = item.trim();
+        match unquote(item) {
+            Some(text) => items.push(text?),
+            None => items.push(item.to_string()),
+        }
+        Ok::<_, String>(())
+    };
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quoted => {
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                push(&value[start..i])?;
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    push(&value[start..])?;
+
+    Ok(items)
+}
+
+/// Makes `config` the configuration used by the days. Only the first call has an effect.
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+fn get(section: &str, key: &str) -> Option<&'static str> {
+    CONFIG.get()?.get(section, key)
+}
+
+/// The value of a parameter, or `default` if it isn't configured.
+///
+/// # Panics
+/// If the configured value can't be parsed.
+pub fn param<T: FromStr>(section: &str, key: &str, default: T) -> T {
+    match get(section, key) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid value `{value}` for {section}.{key}")),
+        None => default,
+    }
+}
+
+/// Like [`param`], for a list written as `[1, 2, 3]`, `1,2,3` or `["a", "b"]`.
+/// Quoted items may contain commas, `\"` and `\\` stand for a quote and a backslash.
+///
+/// # Panics
+/// If the list or one of its items can't be parsed.
+pub fn list<T: FromStr + Clone>(section: &str, key: &str, default: &[T]) -> Vec<T> {
+    match get(section, key) {
+        Some(value) => list_items(value)
+            .unwrap_or_else(|e| panic!("Invalid list for {section}.{key}: {e}"))
+            .iter()
+            .map(|item| {
+                item.parse()
+                    .unwrap_or_else(|_| panic!("Invalid item `{item}` in {section}.{key}"))
+            })
+            .collect(),
+        None => default.to_vec(),
+    }
+}
+
+/// Returns the example input if `input = "test"` is configured for the day.
+pub fn input(section: &str, input: &'static str, test: &'static str) -> &'static str {
+    match get(section, "input") {
+        Some("test") => test,
+        Some("real") | None => input,
+        Some(other) => panic!("Invalid input `{other}` for {section}, expected `test` or `real`"),
+    }
+}
+
+#[test]
+fn test_config() {
+    let mut config = Config::parse(
+        "# parameters\n[d18]\ninput = \"test\"\nsize = 7 # example\n\n[d17]\nprogram = [0, 3, 5]\n",
+    )
+    .unwrap();
+    config.set("d18.fallen=12").unwrap();
+
+    assert_eq!(config.get("d18", "input"), Some("test"));
+    assert_eq!(config.get("d18", "size"), Some("7"));
+    assert_eq!(config.get("d18", "fallen"), Some("12"));
+    assert_eq!(config.get("d17", "program"), Some("[0, 3, 5]"));
+    assert_eq!(config.get("d17", "size"), None);
+
+    assert_eq!(Config::parse("size = 7").unwrap_err().line, 1);
+    assert_eq!(Config::parse("[d1]\n\nsize 7").unwrap_err().line, 3);
+    assert!(config.set("d18size=7").is_err());
+
+    let config = Config::parse("[d04]\nword = \"#A#\" # not a comment inside quotes\n").unwrap();
+    assert_eq!(config.get("d04", "word"), Some("#A#"));
+
+    let config = Config::parse("[d04]\nword = \"a\\\"#\\\\\" # a comment\n").unwrap();
+    assert_eq!(config.get("d04", "word"), Some("a\"#\\"));
+}
+
+#[test]
+fn test_list_items() {
+    assert_eq!(list_items("[0, 3, 5]").unwrap(), ["0", "3", "5"]);
+    assert_eq!(list_items("1,2").unwrap(), ["1", "2"]);
+    assert_eq!(
+        list_items(r#"["a, b", "say \"hi\"", "\\", c]"#).unwrap(),
+        ["a, b", r#"say "hi""#, "\\", "c"]
+    );
+    // Only the surrounding quotes are removed, quotes inside unquoted items stay.
+    assert_eq!(list_items(r#"[x"y]"#).unwrap(), [r#"x"y"#]);
+    assert!(list_items(r#"["open]"#).is_err());
+    assert!(list_items(r#"["\n"]"#).is_err());
+}
@@ -0,0 +1,133 @@
+use std::fmt::Write;
+
+use super::hash::FastSet;
+
+/// What to include when rendering a graph in the DOT language.
+#[derive(Debug, Default, Clone)]
+pub struct DotOptions {
+    /// Label nodes with their weight instead of their index.
+    pub node_labels: bool,
+    /// Label edges with their weight.
+    pub edge_weights: bool,
+    /// Edges (as `(from, to)` node indices) that are drawn in red, e.g. a shortest path.
+    /// In undirected graphs they match in either direction.
+    pub highlight: FastSet<(usize, usize)>,
+}
+
+impl DotOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_node_labels(mut self) -> Self {
+        self.node_labels = true;
+        self
+    }
+
+    pub fn with_edge_weights(mut self) -> Self {
+        self.edge_weights = true;
+        self
+    }
+
+    pub fn with_highlight(mut self, edges: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        self.highlight.extend(edges);
+        self
+    }
+
+    fn is_highlighted(&self, from: usize, to: usize, directed: bool) -> bool {
+        self.highlight.contains(&(from, to)) || (!directed && self.highlight.contains(&(to, from)))
+    }
+}
+
+/// Writes nodes and edges one by one and produces the final DOT source.
+pub struct DotWriter<'a> {
+    options: &'a DotOptions,
+    directed: bool,
+    out: String,
+}
+
+impl<'a> DotWriter<'a> {
+    pub fn digraph(name: &str, options: &'a DotOptions) -> Self {
+        Self::new(true, name, options)
+    }
+
+    pub fn graph(name: &str, options: &'a DotOptions) -> Self {
+        Self::new(false, name, options)
+    }
+
+    fn new(directed: bool, name: &str, options: &'a DotOptions) -> Self {
+        let kind = if directed { "digraph" } else { "graph" };
+        let mut out = String::new();
+        let _ = writeln!(out, "{kind} {name} {{");
+        let _ = writeln!(out, "    node [shape=box, fontname=monospace];");
+
+        Self {
+            options,
+            directed,
+            out,
+        }
+    }
+
+    pub fn node(&mut self, index: usize, label: impl FnOnce() -> String) {
+        let label = if self.options.node_labels {
+            label()
+        } else {
+            index.to_string()
+        };
+
+        let _ = writeln!(self.out, "    n{index} [label=\"{}\"];", escape(&label));
+    }
+
+    pub fn edge(&mut self, from: usize, to: usize, weight: impl FnOnce() -> String) {
+        let mut attributes = Vec::new();
+
+        if self.options.edge_weights {
+            attributes.push(format!("label=\"{}\"", escape(&weight())));
+        }
+
+        if self.options.is_highlighted(from, to, self.directed) {
+            attributes.push("color=red".to_string());
+            attributes.push("penwidth=2".to_string());
+        }
+
+        let connector = if self.directed { "->" } else { "--" };
+        let _ = write!(self.out, "    n{from} {connector} n{to}");
+        if !attributes.is_empty() {
+            let _ = write!(self.out, " [{}]", attributes.join(", "));
+        }
+        let _ = writeln!(self.out, ";");
+    }
+
+    pub fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[test]
+fn test_dot() {
+    let options = DotOptions::new()
+        .with_node_labels()
+        .with_edge_weights()
+        .with_highlight([(1, 0)]);
+
+    let mut dot = DotWriter::digraph("g", &options);
+    dot.node(0, || "say \"hi\"".to_string());
+    dot.edge(0, 1, || "5".to_string());
+    dot.edge(1, 0, || "7".to_string());
+    assert_eq!(
+        dot.finish(),
+        "digraph g {\n    node [shape=box, fontname=monospace];\n    n0 [label=\"say \\\"hi\\\"\"];\n    n0 -> n1 [label=\"5\"];\n    n1 -> n0 [label=\"7\", color=red, penwidth=2];\n}\n"
+    );
+
+    // Undirected edges are highlighted no matter which way round they were given.
+    let mut dot = DotWriter::graph("g", &options);
+    dot.edge(0, 1, || "5".to_string());
+    assert!(dot
+        .finish()
+        .contains("n0 -- n1 [label=\"5\", color=red, penwidth=2];"));
+}
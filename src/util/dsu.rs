@@ -0,0 +1,260 @@
+/// Disjoint set union (union-find) with path compression and union by rank.
+#[derive(Debug, Clone)]
+pub struct DisjointSets {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+}
+
+impl DisjointSets {
+    /// Creates `n` singleton sets `0..n`.
+    pub fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            rank: vec![0; n],
+        }
+    }
+
+    /// Returns the representative of the set containing `x`.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // compress the path, so later lookups are O(1)
+        let mut current = x;
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    /// Merges the sets of `a` and `b`. Returns false if they were already in the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+
+        if a == b {
+            return false;
+        }
+
+        let (root, child) = if self.rank[a] < self.rank[b] {
+            (b, a)
+        } else {
+            (a, b)
+        };
+
+        self.parent[child] = root;
+        if self.rank[root] == self.rank[child] {
+            self.rank[root] += 1;
+        }
+
+        true
+    }
+
+    pub fn is_connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+}
+
+/// Which neighbours of a cell can belong to the same component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Up, down, left and right.
+    Four,
+    /// Also the diagonals.
+    #[cfg(test)]
+    Eight,
+}
+
+impl Connectivity {
+    // Only the neighbours before a cell in row-major order are needed,
+    // the others are visited from the other side.
+    fn previous_neighbours(&self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &[(-1, 0), (0, -1)],
+            #[cfg(test)]
+            Connectivity::Eight => &[(-1, 0), (-1, -1), (0, -1), (1, -1)],
+        }
+    }
+}
+
+/// The result of labelling the connected components of a grid.
+#[derive(Debug, Clone)]
+pub struct Components {
+    /// The component of each cell in row-major order. Labels are numbered
+    /// `0..sizes.len()` in the order the components first appear.
+    pub labels: Vec<usize>,
+    /// The number of cells in each component.
+    pub sizes: Vec<usize>,
+    pub width: usize,
+}
+
+impl Components {
+    /// The cells of each component, in row-major order.
+    pub fn cells(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut cells: Vec<Vec<_>> = self
+            .sizes
+            .iter()
+            .map(|size| Vec::with_capacity(*size))
+            .collect();
+
+        for (i, label) in self.labels.iter().enumerate() {
+            cells[*label].push((i % self.width, i / self.width));
+        }
+
+        cells
+    }
+}
+
+/// Labels the connected components of a `width` x `height` grid.
+///
+/// Two neighbouring cells belong to the same component if `same((x1, y1), (x2, y2))`
+/// returns true. Every cell gets a label, so cells which shouldn't be part of any
+/// component (e.g. walls) end up in components of their own.
+pub fn label_components(
+    width: usize,
+    height: usize,
+    connectivity: Connectivity,
+    mut same: impl FnMut((usize, usize), (usize, usize)) -> bool,
+) -> Components {
+    let mut sets = DisjointSets::new(width * height);
+
+    for y in 0..height {
+        for x in 0..width {
+            for (dx, dy) in connectivity.previous_neighbours() {
+                let (nx, ny) = (x.wrapping_add_signed(*dx), y.wrapping_add_signed(*dy));
+                if nx < width && ny < height && same((x, y), (nx, ny)) {
+                    sets.union(y * width + x, ny * width + nx);
+                }
+            }
+        }
+    }
+
+    let mut root_to_label = vec![usize::MAX; width * height];
+    let mut labels = Vec::with_capacity(width * height);
+    let mut sizes = Vec::new();
+
+    for i in 0..width * height {
+        let root = sets.find(i);
+        if root_to_label[root] == usize::MAX {
+            root_to_label[root] = sizes.len();
+            sizes.push(0);
+        }
+
+        let label = root_to_label[root];
+        sizes[label] += 1;
+        labels.push(label);
+    }
+
+    Components {
+        labels,
+        sizes,
+        width,
+    }
+}
+
+#[test]
+fn test_disjoint_sets() {
+    use super::math::random_numbers;
+
+    // Compare with relabelling a whole set on every union.
+    let n = 64;
+    let mut sets = DisjointSets::new(n);
+    let mut naive: Vec<usize> = (0..n).collect();
+    let mut numbers = random_numbers(5).map(|r| r as usize % n);
+
+    for _ in 0..200 {
+        let (a, b) = (numbers.next().unwrap(), numbers.next().unwrap());
+        assert_eq!(sets.union(a, b), naive[a] != naive[b]);
+
+        let (from, to) = (naive[b], naive[a]);
+        naive
+            .iter_mut()
+            .filter(|set| **set == from)
+            .for_each(|set| *set = to);
+
+        let (c, d) = (numbers.next().unwrap(), numbers.next().unwrap());
+        assert_eq!(sets.is_connected(c, d), naive[c] == naive[d]);
+        assert_eq!(sets.find(c) == sets.find(d), naive[c] == naive[d]);
+    }
+}
+
+#[test]
+fn test_label_components() {
+    use super::math::random_numbers;
+
+    fn same(grid: &[bool], width: usize) -> impl Fn((usize, usize), (usize, usize)) -> bool + '_ {
+        move |(x1, y1), (x2, y2)| grid[y1 * width + x1] == grid[y2 * width + x2]
+    }
+
+    // A checkerboard only connects along the diagonals.
+    let board = [true, false, false, true];
+    let four = label_components(2, 2, Connectivity::Four, same(&board, 2));
+    assert_eq!((four.labels, four.sizes), (vec![0, 1, 2, 3], vec![1; 4]));
+    let eight = label_components(2, 2, Connectivity::Eight, same(&board, 2));
+    assert_eq!((eight.labels, eight.sizes), (vec![0, 1, 1, 0], vec![2, 2]));
+
+    // Compare with a flood fill on random grids.
+    let (width, height) = (13, 7);
+    let mut numbers = random_numbers(6);
+    for _ in 0..50 {
+        let grid: Vec<bool> = numbers
+            .by_ref()
+            .take(width * height)
+            .map(|n| n % 3 == 0)
+            .collect();
+
+        for connectivity in [Connectivity::Four, Connectivity::Eight] {
+            let components = label_components(width, height, connectivity, same(&grid, width));
+            let mut labels = vec![usize::MAX; width * height];
+            let mut sizes = Vec::new();
+
+            for start in 0..width * height {
+                if labels[start] != usize::MAX {
+                    continue;
+                }
+
+                let label = sizes.len();
+                sizes.push(0);
+                labels[start] = label;
+                let mut stack = vec![start];
+
+                while let Some(i) = stack.pop() {
+                    sizes[label] += 1;
+                    let (x, y) = (i % width, i / width);
+                    for (dx, dy) in [
+                        (-1, -1),
+                        (0, -1),
+                        (1, -1),
+                        (-1, 0),
+                        (1, 0),
+                        (-1, 1),
+                        (0, 1),
+                        (1, 1),
+                    ] {
+                        if connectivity == Connectivity::Four && dx != 0 && dy != 0 {
+                            continue;
+                        }
+
+                        let (nx, ny) = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+                        if nx >= width || ny >= height {
+                            continue;
+                        }
+
+                        let next = ny * width + nx;
+                        if labels[next] == usize::MAX && grid[next] == grid[i] {
+                            labels[next] = label;
+                            stack.push(next);
+                        }
+                    }
+                }
+            }
+
+            assert_eq!(components.labels, labels);
+            assert_eq!(components.sizes, sizes);
+        }
+    }
+}
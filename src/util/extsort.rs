@@ -0,0 +1,222 @@
+//! Sorting more numbers than fit into memory.
+//!
+//! Values are collected until the budget is reached, then the sorted run is written to a
+//! temporary file. Finishing merges all runs with a heap, so only one value per run is
+//! held in memory while iterating. The merged runs can be rewound to iterate them again.
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+// A sorted run spilled to a temporary file, which is removed on drop.
+struct Run {
+    path: PathBuf,
+    reader: BufReader<File>,
+}
+
+impl Run {
+    fn write(values: &[u64]) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "aoc24rust-{}-{}.run",
+            std::process::id(),
+            NEXT_RUN.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        let mut writer = BufWriter::new(file);
+        for value in values {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        let mut file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+
+        Ok(Run {
+            path,
+            reader: BufReader::new(file),
+        })
+    }
+
+    fn rewind(&mut self) -> io::Result<()> {
+        self.reader.seek(SeekFrom::Start(0)).map(|_| ())
+    }
+
+    fn next(&mut self) -> io::Result<Option<u64>> {
+        let mut bytes = [0; 8];
+        match self.reader.read_exact(&mut bytes) {
+            Ok(()) => Ok(Some(u64::from_le_bytes(bytes))),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+enum Source {
+    /// The values and the index of the next one.
+    Memory(Vec<u64>, usize),
+    File(Run),
+}
+
+impl Source {
+    fn rewind(&mut self) -> io::Result<()> {
+        match self {
+            Source::Memory(_, next) => {
+                *next = 0;
+                Ok(())
+            }
+            Source::File(run) => run.rewind(),
+        }
+    }
+
+    fn next(&mut self) -> io::Result<Option<u64>> {
+        match self {
+            Source::Memory(values, next) => {
+                let value = values.get(*next).copied();
+                *next += 1;
+                Ok(value)
+            }
+            Source::File(run) => run.next(),
+        }
+    }
+}
+
+/// Sorts `u64`s while keeping at most `budget` of them in memory.
+pub struct ExternalSorter {
+    budget: usize,
+    buffer: Vec<u64>,
+    runs: Vec<Run>,
+    len: usize,
+}
+
+impl ExternalSorter {
+    pub fn new(budget: usize) -> Self {
+        assert!(
+            budget > 0,
+            "The memory budget has to hold at least one value"
+        );
+
+        Self {
+            budget,
+            buffer: Vec::new(),
+            runs: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, value: u64) -> io::Result<()> {
+        self.buffer.push(value);
+        self.len += 1;
+
+        if self.buffer.len() >= self.budget {
+            self.buffer.sort_unstable();
+            self.runs.push(Run::write(&self.buffer)?);
+            self.buffer.clear();
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// How many runs were written to disk so far.
+    #[cfg(test)]
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Returns all pushed values in ascending order.
+    pub fn finish(mut self) -> io::Result<Sorted> {
+        self.buffer.sort_unstable();
+
+        let mut sources: Vec<Source> = self.runs.into_iter().map(Source::File).collect();
+        sources.push(Source::Memory(self.buffer, 0));
+
+        let mut sorted = Sorted {
+            heap: BinaryHeap::with_capacity(sources.len()),
+            sources,
+        };
+        sorted.fill_heap()?;
+        Ok(sorted)
+    }
+}
+
+/// The merged runs of an [`ExternalSorter`].
+pub struct Sorted {
+    sources: Vec<Source>,
+    heap: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl Sorted {
+    /// Starts over with the smallest value.
+    pub fn rewind(&mut self) -> io::Result<()> {
+        for source in &mut self.sources {
+            source.rewind()?;
+        }
+        self.fill_heap()
+    }
+
+    fn fill_heap(&mut self) -> io::Result<()> {
+        self.heap.clear();
+        for (i, source) in self.sources.iter_mut().enumerate() {
+            if let Some(value) = source.next()? {
+                self.heap.push(Reverse((value, i)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Sorted {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((value, i)) = self.heap.pop()?;
+
+        match self.sources[i].next() {
+            Ok(Some(next)) => self.heap.push(Reverse((next, i))),
+            Ok(None) => (),
+            Err(e) => return Some(Err(e)),
+        }
+
+        Some(Ok(value))
+    }
+}
+
+#[test]
+fn test_external_sort() {
+    let values: Vec<u64> = (0..100).map(|i| (i * 37) % 101).collect();
+    let mut sorter = ExternalSorter::new(16);
+    for value in &values {
+        sorter.push(*value).unwrap();
+    }
+    assert_eq!(sorter.spilled_runs(), 6);
+
+    let mut sorted = sorter.finish().unwrap();
+    let first: Vec<u64> = sorted.by_ref().take(30).map(Result::unwrap).collect();
+    let mut expected = values;
+    expected.sort();
+    assert_eq!(first, expected[..30]);
+
+    // Rewinding starts over, even in the middle of the runs.
+    sorted.rewind().unwrap();
+    let all: Vec<u64> = sorted.map(Result::unwrap).collect();
+    assert_eq!(all, expected);
+}
@@ -1,4 +1,9 @@
+#[allow(dead_code)] // kept from the start, no day uses it
 pub mod stackgraph {
+    use std::fmt::Display;
+
+    use crate::util::dot::{DotOptions, DotWriter};
+
     #[derive(Clone, Copy, Default, Debug)]
     struct Node<N> {
         weight: N,
@@ -130,6 +135,22 @@ pub mod stackgraph {
         }
     }
 
+    impl<N: Display, E: Display, const NN: usize, const NE: usize> Graph<N, E, NN, NE> {
+        pub fn to_dot(&self, options: &DotOptions) -> String {
+            let mut dot = DotWriter::graph("stackgraph", options);
+
+            for (i, node) in self.nodes[..self.node_count].iter().enumerate() {
+                dot.node(i, || node.weight.to_string());
+            }
+
+            for edge in &self.edges[..self.edge_count] {
+                dot.edge(edge.from, edge.to, || edge.weight.to_string());
+            }
+
+            dot.finish()
+        }
+    }
+
     pub struct Neighbors<'a, N, E, const NN: usize, const NE: usize> {
         graph: &'a Graph<N, E, NN, NE>,
         edge: Option<usize>,
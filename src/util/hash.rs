@@ -0,0 +1,102 @@
+use std::collections::{HashMap, HashSet};
+#[cfg(not(feature = "siphash"))]
+use std::hash::Hasher;
+
+/// The hasher used by rustc (FxHash). Much faster than SipHash for small keys like
+/// integers and points, but not resistant against collision attacks, which doesn't
+/// matter for puzzle inputs. Not compiled with the `siphash` feature.
+#[cfg(not(feature = "siphash"))]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+#[cfg(not(feature = "siphash"))]
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+#[cfg(not(feature = "siphash"))]
+impl FxHasher {
+    #[inline]
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+#[cfg(not(feature = "siphash"))]
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+
+        for chunk in &mut chunks {
+            self.add_to_hash(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+
+        let mut rest = [0; 8];
+        let remainder = chunks.remainder();
+        if !remainder.is_empty() {
+            rest[..remainder.len()].copy_from_slice(remainder);
+            self.add_to_hash(u64::from_le_bytes(rest));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// Builds the hasher for [`FastMap`] and [`FastSet`]. With the `siphash` feature
+/// this is the std default, so both can be benchmarked against each other.
+#[cfg(not(feature = "siphash"))]
+pub type FastBuildHasher = std::hash::BuildHasherDefault<FxHasher>;
+#[cfg(feature = "siphash")]
+pub type FastBuildHasher = std::collections::hash_map::RandomState;
+
+#[cfg(not(feature = "siphash"))]
+pub const HASHER_NAME: &str = "FxHash";
+#[cfg(feature = "siphash")]
+pub const HASHER_NAME: &str = "SipHash";
+
+/// Create them with `FastMap::default()`, `new` only exists for the std hasher.
+pub type FastMap<K, V> = HashMap<K, V, FastBuildHasher>;
+pub type FastSet<T> = HashSet<T, FastBuildHasher>;
+
+#[cfg(not(feature = "siphash"))]
+#[test]
+fn test_fx_hasher() {
+    use std::hash::{BuildHasher, BuildHasherDefault};
+
+    let build = BuildHasherDefault::<FxHasher>::default();
+    assert_eq!(build.hash_one((1, 2)), build.hash_one((1, 2)));
+    assert_ne!(build.hash_one((1, 2)), build.hash_one((2, 1)));
+    assert_ne!(build.hash_one("abcdefghi"), build.hash_one("abcdefgh"));
+
+    let set: FastSet<_> = (0..1000).map(|i| i % 100).collect();
+    assert_eq!(set.len(), 100);
+}
@@ -0,0 +1,199 @@
+//! Cleans up puzzle inputs and checks their shape before a day parses them.
+//!
+//! Inputs are normalized to `\n` line endings without trailing whitespace, ending in
+//! exactly one newline, so copying an input on Windows or from a browser doesn't change
+//! the answer. Each day describes its input as a list of [`Block`]s, one per section
+//! separated by blank lines, and everything which doesn't match is reported with its
+//! line and column.
+
+use std::{borrow::Cow, sync::Mutex};
+
+use super::parse::{self, ParseError};
+
+/// The expected shape of a section of the input.
+///
+/// Character sets are written like regex classes: `"0-9,"` allows digits and commas,
+/// and a `-` at the start or end stands for itself.
+#[derive(Debug, Clone, Copy)]
+pub enum Block {
+    /// A rectangular grid of the given characters.
+    Grid(&'static str),
+    /// Lines of any length made of the given characters.
+    Lines(&'static str),
+    /// Anything goes.
+    Text,
+}
+
+// Normalized inputs which had to be copied, by the address of the original.
+static NORMALIZED: Mutex<Vec<(usize, &'static str)>> = Mutex::new(Vec::new());
+
+/// Returns `input` with `\n` line endings, without trailing whitespace on any line,
+/// without blank lines at the end and with a single final newline.
+pub fn normalize(input: &str) -> Cow<'_, str> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let trimmed = input.trim_end();
+    let is_normal = input.len() == trimmed.len() + 1
+        && input.ends_with('\n')
+        && !input.contains('\r')
+        && trimmed.lines().all(|line| line.trim_end() == line);
+
+    if is_normal {
+        return Cow::Borrowed(input);
+    }
+
+    let mut normalized = String::with_capacity(input.len());
+    for line in trimmed.lines() {
+        normalized.push_str(line.trim_end());
+        normalized.push('\n');
+    }
+    Cow::Owned(normalized)
+}
+
+fn allowed(set: &str, c: char) -> bool {
+    let set: Vec<char> = set.chars().collect();
+    let mut i = 0;
+
+    while i < set.len() {
+        if set[i] == c {
+            return true;
+        }
+        if i + 2 < set.len() && set[i + 1] == '-' {
+            if (set[i]..=set[i + 2]).contains(&c) {
+                return true;
+            }
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+
+    false
+}
+
+fn check_chars(line: &str, number: usize, set: &str, errors: &mut Vec<ParseError>) {
+    for (x, c) in line.chars().enumerate() {
+        if !allowed(set, c) {
+            errors.push(ParseError::new(
+                number,
+                x + 1,
+                format!(
+                    "unexpected character `{}`, expected one of `{set}`",
+                    c.escape_debug()
+                ),
+            ));
+        }
+    }
+}
+
+/// Checks a normalized input against `format`. The last block also applies to any
+/// further sections, so `&[Block::Lines(..)]` accepts any number of sections.
+pub fn lint(input: &str, format: &[Block]) -> Vec<ParseError> {
+    let mut errors = Vec::new();
+    let sections = parse::sections(input);
+
+    if sections.is_empty() {
+        errors.push(ParseError::new(1, 1, "input is empty"));
+        return errors;
+    }
+    if sections.len() < format.len() {
+        let line = input.lines().count() + 1;
+        errors.push(ParseError::new(
+            line,
+            1,
+            format!(
+                "expected {} sections separated by blank lines, found {}",
+                format.len(),
+                sections.len()
+            ),
+        ));
+    }
+
+    for (i, section) in sections.iter().enumerate() {
+        let block = format[i.min(format.len() - 1)];
+        let mut width = None;
+
+        for (y, line) in section.text.lines().enumerate() {
+            let number = section.first_line + y;
+
+            match block {
+                Block::Grid(set) => {
+                    check_chars(line, number, set, &mut errors);
+
+                    let row_width = line.chars().count();
+                    match width {
+                        None => width = Some(row_width),
+                        Some(width) if width != row_width => errors.push(ParseError::new(
+                            number,
+                            row_width.min(width) + 1,
+                            format!("row has width {row_width}, expected {width}"),
+                        )),
+                        _ => (),
+                    }
+                }
+                Block::Lines(set) => check_chars(line, number, set, &mut errors),
+                Block::Text => (),
+            }
+        }
+    }
+
+    errors
+}
+
+/// Normalizes and lints the input of `day`.
+///
+/// # Panics
+/// If the input doesn't match `format`, listing the first problems found.
+pub fn prepare(day: &str, input: &'static str, format: &[Block]) -> &'static str {
+    let address = input.as_ptr() as usize;
+    let cached = NORMALIZED
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(original, _)| *original == address)
+        .map(|(_, normalized)| *normalized);
+
+    if let Some(normalized) = cached {
+        return normalized;
+    }
+
+    let normalized = match normalize(input) {
+        Cow::Borrowed(input) => input,
+        Cow::Owned(normalized) => Box::leak(normalized.into_boxed_str()),
+    };
+
+    let errors = lint(normalized, format);
+    if !errors.is_empty() {
+        let mut message = format!("Invalid input for {day}:");
+        for error in errors.iter().take(10) {
+            message.push_str(&format!("\n    {error}"));
+        }
+        if errors.len() > 10 {
+            message.push_str(&format!("\n    ... and {} more", errors.len() - 10));
+        }
+        panic!("{message}");
+    }
+
+    NORMALIZED.lock().unwrap().push((address, normalized));
+    normalized
+}
+
+#[test]
+fn test_normalize_and_lint() {
+    assert!(matches!(normalize("1 2\n3 4\n"), Cow::Borrowed(_)));
+    assert_eq!(normalize("1 2 \r\n3 4\r\n\r\n"), "1 2\n3 4\n");
+    assert_eq!(normalize("#.\n.#"), "#.\n.#\n");
+
+    let format = [Block::Grid("#.@"), Block::Lines("<>^v")];
+    assert!(lint("#.\n@#\n\n<>\n^\n", &format).is_empty());
+
+    let errors = lint("#.\n@x#\n\n<>\n", &format);
+    assert_eq!(errors.len(), 2);
+    assert_eq!((errors[0].line, errors[0].column), (2, 2));
+    assert_eq!((errors[1].line, errors[1].column), (2, 3));
+    assert_eq!(
+        lint("#.\n", &format)[0].message,
+        "expected 2 sections separated by blank lines, found 1"
+    );
+
+    assert!(lint("p=0,4 v=3,-3\n", &[Block::Lines("0-9pv=, -")]).is_empty());
+}
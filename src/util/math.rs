@@ -0,0 +1,426 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+/// Number of decimal digits of `n`. Zero has one digit.
+pub fn digit_count(n: u64) -> u32 {
+    n.checked_ilog10().unwrap_or(0) + 1
+}
+
+/// Splits a number with an even number of digits into its left and right half,
+/// e.g. `1234` into `(12, 34)` and `1000` into `(10, 0)`.
+pub fn split_digits(n: u64) -> Option<(u64, u64)> {
+    let digits = digit_count(n);
+
+    if digits.is_multiple_of(2) {
+        let ten = 10u64.pow(digits / 2);
+        Some((n / ten, n % ten))
+    } else {
+        None
+    }
+}
+
+/// Appends the decimal digits of `b` to `a`, e.g. `concat(12, 345) == Some(12345)`.
+/// Returns `None` on overflow.
+pub fn concat(a: u64, b: u64) -> Option<u64> {
+    10u64
+        .checked_pow(digit_count(b))
+        .and_then(|shift| a.checked_mul(shift))
+        .and_then(|shifted| shifted.checked_add(b))
+}
+
+/// The inverse of [`concat`]: removes the decimal digits of `b` from the end of `n`,
+/// e.g. `split_concat(12345, 345) == Some(12)`.
+pub fn split_concat(n: u64, b: u64) -> Option<u64> {
+    let shift = 10u64.checked_pow(digit_count(b))?;
+    (n % shift == b).then_some(n / shift)
+}
+
+/// The `k`-th root of `n`, if it is a whole number.
+pub fn exact_root(n: u64, k: u32) -> Option<u64> {
+    match k {
+        0 => return None,
+        1 => return Some(n),
+        _ => (),
+    }
+
+    // Above 2^53 `n` doesn't fit into a float exactly, so the estimate is only close.
+    // Move it until `root^k <= n < (root + 1)^k`.
+    let mut root = (n as f64).powf(1.0 / k as f64) as u64;
+    while root.checked_pow(k).is_none_or(|power| power > n) {
+        root -= 1;
+    }
+    while (root + 1).checked_pow(k).is_some_and(|power| power <= n) {
+        root += 1;
+    }
+
+    (root.pow(k) == n).then_some(root)
+}
+
+/// Greatest common divisor. Unsigned, as the gcd of `i64::MIN` and `0` is `2^63`.
+#[cfg(test)]
+pub fn gcd(a: i64, b: i64) -> u64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+/// Least common multiple, always non-negative. Returns `None` on overflow.
+#[cfg(test)]
+pub fn lcm(a: i64, b: i64) -> Option<i64> {
+    if a == 0 || b == 0 {
+        Some(0)
+    } else {
+        let lcm = (a as i128 / gcd(a, b) as i128 * b as i128).abs();
+        i64::try_from(lcm).ok()
+    }
+}
+
+/// Returns `(g, x, y)` with `a * x + b * y == g`, where `g` is the gcd of `a` and `b`.
+/// `g` is non-negative except for the gcd `2^63` of `i64::MIN` and `0` or `i64::MIN`,
+/// which wraps around to `i64::MIN`.
+#[cfg(test)]
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (g, x, y) = extended_gcd_i128(a as i128, b as i128);
+    (g as i64, x as i64, y as i64)
+}
+
+fn extended_gcd_i128(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// The inverse of `a` modulo `m` in `0..m`, if `a` and `m` are coprime.
+#[cfg(test)]
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    if m <= 0 {
+        return None;
+    }
+
+    let (g, x, _) = extended_gcd_i128(a as i128, m as i128);
+    if g == 1 {
+        Some(x.rem_euclid(m as i128) as i64)
+    } else {
+        None
+    }
+}
+
+/// Chinese remainder theorem: finds `x` with `x ≡ r (mod m)` for every `(r, m)`.
+///
+/// The moduli do not have to be coprime. Returns `(x, lcm)` with `x` in `0..lcm`,
+/// or `None` if the congruences contradict each other or the lcm overflows.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    let mut x: i128 = 0;
+    let mut m: i128 = 1;
+
+    for &(r, n) in congruences {
+        let n = n as i128;
+        if n <= 0 {
+            return None;
+        }
+
+        let r = (r as i128).rem_euclid(n);
+        let (g, p, _) = extended_gcd_i128(m, n);
+        let diff = r - x;
+
+        if diff % g != 0 {
+            return None;
+        }
+
+        let step = n / g;
+        let k = (diff / g % step * p % step).rem_euclid(step);
+        x += m * k;
+        m *= step;
+        x = x.rem_euclid(m);
+
+        if m > i64::MAX as i128 {
+            return None;
+        }
+    }
+
+    Some((x as i64, m as i64))
+}
+
+/// An exact fraction, always stored in lowest terms with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    /// # Panics
+    /// If `den` is zero.
+    pub fn new(num: i128, den: i128) -> Self {
+        assert_ne!(den, 0, "Denominator of a rational must not be zero");
+
+        let g = gcd_i128(num, den);
+        let sign = den.signum();
+
+        Self {
+            num: sign * num / g,
+            den: sign * den / g,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn to_integer(self) -> Option<i64> {
+        if self.den == 1 {
+            self.num.try_into().ok()
+        } else {
+            None
+        }
+    }
+}
+
+fn gcd_i128(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a.max(1) as i128
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Self {
+        Self {
+            num: value as i128,
+            den: 1,
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.num * rhs.den + rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Mul for Rational {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl Div for Rational {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Self::new(self.num * rhs.den, self.den * rhs.num)
+    }
+}
+
+impl Neg for Rational {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+/// Solves the square system `a * x = b` exactly with Gaussian elimination.
+///
+/// Returns `None` if the system has no unique solution.
+pub fn solve_linear<const N: usize>(a: [[i64; N]; N], b: [i64; N]) -> Option<[Rational; N]> {
+    let mut rows: [[Rational; N]; N] = a.map(|row| row.map(Rational::from));
+    let mut rhs = b.map(Rational::from);
+
+    for col in 0..N {
+        let pivot = (col..N).find(|row| !rows[*row][col].is_zero())?;
+        rows.swap(col, pivot);
+        rhs.swap(col, pivot);
+
+        for row in 0..N {
+            if row != col && !rows[row][col].is_zero() {
+                let pivot_row = rows[col];
+                let factor = rows[row][col] / pivot_row[col];
+                for (value, pivot) in rows[row].iter_mut().zip(pivot_row).skip(col) {
+                    *value = *value - factor * pivot;
+                }
+                rhs[row] = rhs[row] - factor * rhs[col];
+            }
+        }
+    }
+
+    Some(core::array::from_fn(|i| rhs[i] / rows[i][i]))
+}
+
+/// Minimal xorshift generator, so the properties below can be checked on many inputs.
+#[cfg(test)]
+pub fn random_numbers(seed: u64) -> impl Iterator<Item = u64> {
+    let mut state = seed;
+    std::iter::from_fn(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        Some(state)
+    })
+}
+
+#[test]
+fn test_digits() {
+    for n in random_numbers(1).take(10_000).map(|n| n >> (n % 64)) {
+        assert_eq!(digit_count(n) as usize, n.to_string().len());
+
+        match split_digits(n) {
+            Some((left, right)) => {
+                let s = n.to_string();
+                assert_eq!(left.to_string(), s[..s.len() / 2]);
+                assert_eq!(right, s[s.len() / 2..].parse().unwrap());
+            }
+            None => assert_eq!(digit_count(n) % 2, 1),
+        }
+
+        let b = n % 100_000;
+        let expected = format!("{n}{b}").parse::<u64>().ok();
+        assert_eq!(concat(n, b), expected);
+        if let Some(joined) = expected {
+            assert_eq!(split_concat(joined, b), Some(n));
+        }
+
+        let k = (n % 5) as u32 + 1;
+        let root = (n >> (n % 64)) % 1000;
+        if let Some(power) = root.checked_pow(k) {
+            assert_eq!(exact_root(power, k), Some(root));
+        }
+    }
+
+    // Near the top floats can't tell neighbouring numbers apart.
+    assert_eq!(exact_root(u64::MAX, 1), Some(u64::MAX));
+    assert_eq!(exact_root((1 << 60) + 3, 1), Some((1 << 60) + 3));
+    assert_eq!(exact_root(u64::MAX, 2), None);
+    assert_eq!(exact_root(u64::MAX, 64), None);
+    for k in 2..64 {
+        // The largest root whose power still fits.
+        let mut root = (u64::MAX as f64).powf(1.0 / k as f64) as u64 + 2;
+        while root.checked_pow(k).is_none() {
+            root -= 1;
+        }
+
+        let power = root.pow(k);
+        assert_eq!(exact_root(power, k), Some(root), "{root}^{k}");
+        assert_eq!(exact_root(power - 1, k), None, "{root}^{k} - 1");
+        assert_eq!(exact_root(power + 1, k), None, "{root}^{k} + 1");
+    }
+}
+
+#[test]
+fn test_gcd() {
+    let mut numbers = random_numbers(2).map(|n| (n % 2_000_001) as i64 - 1_000_000);
+
+    for _ in 0..10_000 {
+        let (a, b) = (numbers.next().unwrap(), numbers.next().unwrap());
+        let g = gcd(a, b) as i64;
+        let (g_ext, x, y) = extended_gcd(a, b);
+
+        assert_eq!(g, g_ext);
+        assert_eq!(a * x + b * y, g);
+        if g != 0 {
+            assert_eq!((a % g, b % g), (0, 0));
+            let l = lcm(a, b).unwrap();
+            assert_eq!((l % a, l % b), (0, 0));
+            assert_eq!(l * g, (a * b).abs());
+        }
+
+        if b > 1 {
+            match mod_inverse(a, b) {
+                Some(inv) => assert_eq!((a * inv).rem_euclid(b), 1 % b),
+                None => assert_ne!(g, 1),
+            }
+        }
+    }
+
+    assert_eq!(gcd(i64::MIN, 0), 1 << 63);
+    assert_eq!(gcd(i64::MIN, i64::MIN), 1 << 63);
+    assert_eq!(gcd(i64::MIN, 6), 2);
+    assert_eq!(lcm(i64::MIN, 2), None);
+    assert_eq!(lcm(i64::MIN + 1, -1), Some(i64::MAX));
+}
+
+#[test]
+fn test_crt() {
+    let mut numbers = random_numbers(3);
+
+    for _ in 0..10_000 {
+        let moduli: Vec<i64> = (0..3)
+            .map(|_| (numbers.next().unwrap() % 200 + 1) as i64)
+            .collect();
+        let x = (numbers.next().unwrap() % 1_000_000) as i64;
+        let congruences: Vec<_> = moduli.iter().map(|m| (x % m, *m)).collect();
+
+        let (solution, modulus) = crt(&congruences).unwrap();
+        assert_eq!(modulus, moduli.iter().fold(1, |l, m| lcm(l, *m).unwrap()));
+        assert_eq!(solution, x % modulus);
+    }
+
+    assert_eq!(crt(&[(0, 4), (1, 6)]), None);
+}
+
+#[test]
+fn test_solve_linear() {
+    let mut numbers = random_numbers(4).map(|n| (n % 41) as i64 - 20);
+
+    for _ in 0..10_000 {
+        let a: [[i64; 3]; 3] =
+            core::array::from_fn(|_| core::array::from_fn(|_| numbers.next().unwrap()));
+        let x: [i64; 3] = core::array::from_fn(|_| numbers.next().unwrap());
+        let b = core::array::from_fn(|row| (0..3).map(|col| a[row][col] * x[col]).sum());
+
+        if let Some(solution) = solve_linear(a, b) {
+            assert_eq!(solution.map(|r| r.to_integer()), x.map(Some));
+        }
+    }
+
+    let solution = solve_linear([[2, 0], [0, 4]], [1, 2]).unwrap();
+    assert_eq!(solution, [Rational::new(1, 2), Rational::new(1, 2)]);
+    assert_eq!(solve_linear([[1, 2], [2, 4]], [1, 2]), None);
+}
@@ -0,0 +1,341 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+};
+
+use super::{
+    hash::FastMap,
+    trace::{self, Event, Value},
+};
+
+/// Where a [`Memo`] keeps its results.
+pub trait Store<K, V> {
+    fn get(&self, key: &K) -> Option<&V>;
+    fn insert(&mut self, key: K, value: V);
+    fn len(&self) -> usize;
+}
+
+impl<K: Hash + Eq, V, H: BuildHasher> Store<K, V> for HashMap<K, V, H> {
+    fn get(&self, key: &K) -> Option<&V> {
+        HashMap::get(self, key)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        HashMap::insert(self, key, value);
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+}
+
+/// Stores results for the keys `0..size` in a fixed-size array, which avoids hashing
+/// when the keys are small integers.
+///
+/// # Panics
+/// When a key outside of `0..size` is inserted.
+#[derive(Debug, Clone)]
+pub struct DenseStore<V> {
+    values: Box<[Option<V>]>,
+    len: usize,
+}
+
+impl<V> DenseStore<V> {
+    pub fn new(size: usize) -> Self {
+        Self {
+            values: (0..size).map(|_| None).collect(),
+            len: 0,
+        }
+    }
+}
+
+impl<V> Store<usize, V> for DenseStore<V> {
+    fn get(&self, key: &usize) -> Option<&V> {
+        self.values.get(*key).and_then(|value| value.as_ref())
+    }
+
+    fn insert(&mut self, key: usize, value: V) {
+        assert!(
+            key < self.values.len(),
+            "Key {key} is out of range for a DenseStore of size {}",
+            self.values.len()
+        );
+        if self.values[key].replace(value).is_none() {
+            self.len += 1;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// How often a [`Memo`] could answer from its cache.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl Stats {
+    pub fn lookups(&self) -> usize {
+        self.hits + self.misses
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups() == 0 {
+            0.
+        } else {
+            self.hits as f64 / self.lookups() as f64
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses ({:.1}% hit rate)",
+            self.hits,
+            self.misses,
+            self.hit_rate() * 100.
+        )
+    }
+}
+
+/// A cache for the results of a function, which counts hits and misses.
+///
+/// Keys may borrow from the input (e.g. `&str`), as long as the memo doesn't outlive it.
+pub struct Memo<K, V, S = FastMap<K, V>> {
+    store: S,
+    stats: Stats,
+    _kv: PhantomData<(K, V)>,
+}
+
+impl<K, V, S: Default> Default for Memo<K, V, S> {
+    fn default() -> Self {
+        Self {
+            store: S::default(),
+            stats: Stats::default(),
+            _kv: PhantomData,
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Memo<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<V> Memo<usize, V, DenseStore<V>> {
+    /// A memo for the keys `0..size`, see [`DenseStore`].
+    pub fn dense(size: usize) -> Self {
+        Self {
+            store: DenseStore::new(size),
+            stats: Stats::default(),
+            _kv: PhantomData,
+        }
+    }
+}
+
+impl<K, V: Clone, S: Store<K, V>> Memo<K, V, S> {
+    /// Returns the cached value for `key`, or computes and caches it with `f`.
+    ///
+    /// `f` gets the memo itself, so it can recurse into `get_or_insert_with`.
+    pub fn get_or_insert_with(&mut self, key: K, f: impl FnOnce(&mut Self) -> V) -> V {
+        if let Some(value) = self.lookup(&key) {
+            value
+        } else {
+            let value = f(self);
+            self.store.insert(key, value.clone());
+            value
+        }
+    }
+
+    /// Returns the cached value for `key` and records the hit or miss.
+    pub fn lookup(&mut self, key: &K) -> Option<V> {
+        let value = self.store.get(key).cloned();
+
+        if value.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+
+        value
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+}
+
+/// Trace event with the statistics of a memoized function.
+struct Report {
+    function: &'static str,
+    entries: usize,
+    stats: Stats,
+}
+
+impl Event for Report {
+    const KIND: &'static str = "memo";
+
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("function", self.function.into()),
+            ("entries", self.entries.into()),
+            ("hits", self.stats.hits.into()),
+            ("misses", self.stats.misses.into()),
+            (
+                "hit_rate",
+                format!("{:.1}%", self.stats.hit_rate() * 100.).into(),
+            ),
+        ]
+    }
+}
+
+/// A recursive function together with the memo of its results. See [`memoize`].
+pub struct Memoized<K, V, F, S = FastMap<K, V>> {
+    f: F,
+    memo: Memo<K, V, S>,
+}
+
+/// Turns a plain recursive function into a memoized one.
+///
+/// Instead of calling itself, `f` calls the function it gets as first argument:
+///
+/// ```ignore
+/// let mut fib = memoize(|fib: &mut dyn FnMut(u64) -> u64, n: u64| {
+///     if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+/// });
+/// assert_eq!(fib.call(90), 2880067194370816120);
+/// ```
+///
+/// With borrowed keys, name the key type up front (`memoize::<&str, usize, _>(...)`),
+/// otherwise the closure is inferred to accept keys of any lifetime.
+pub fn memoize<K, V, F>(f: F) -> Memoized<K, V, F>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+{
+    Memoized {
+        f,
+        memo: Memo::new(),
+    }
+}
+
+/// Like [`memoize`], but caches the results for the keys `0..size` in an array.
+pub fn memoize_dense<V, F>(size: usize, f: F) -> Memoized<usize, V, F, DenseStore<V>>
+where
+    V: Clone,
+    F: Fn(&mut dyn FnMut(usize) -> V, usize) -> V,
+{
+    Memoized {
+        f,
+        memo: Memo::dense(size),
+    }
+}
+
+impl<K, V, F, S> Memoized<K, V, F, S>
+where
+    K: Clone,
+    V: Clone,
+    F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+    S: Store<K, V>,
+{
+    pub fn call(&mut self, key: K) -> V {
+        call_memoized(&self.f, &mut self.memo, key)
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.memo.stats()
+    }
+
+    /// Emits the statistics as a trace event, shown with `--explain`.
+    pub fn report(&self, function: &'static str) {
+        trace::emit(|| Report {
+            function,
+            entries: self.memo.len(),
+            stats: self.stats(),
+        });
+    }
+}
+
+fn call_memoized<K, V, F, S>(f: &F, memo: &mut Memo<K, V, S>, key: K) -> V
+where
+    K: Clone,
+    V: Clone,
+    F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+    S: Store<K, V>,
+{
+    memo.get_or_insert_with(key.clone(), |memo| {
+        f(&mut |next| call_memoized(f, memo, next), key)
+    })
+}
+
+#[test]
+fn test_memoize() {
+    let mut fib = memoize(
+        |fib: &mut dyn FnMut(u64) -> u64, n: u64| {
+            if n < 2 {
+                n
+            } else {
+                fib(n - 1) + fib(n - 2)
+            }
+        },
+    );
+    assert_eq!(fib.call(90), 2880067194370816120);
+    // Every `fib(n)` is computed once, `fib(n - 2)` is a hit for all but the bottom call.
+    assert_eq!(
+        fib.stats(),
+        Stats {
+            hits: 88,
+            misses: 91
+        }
+    );
+    fib.call(50);
+    assert_eq!(
+        fib.stats(),
+        Stats {
+            hits: 89,
+            misses: 91
+        }
+    );
+
+    // Keys borrowed from a local string.
+    let text = String::from("abcab");
+    let mut suffixes = memoize::<&str, usize, _>(|count, text| {
+        if text.is_empty() {
+            0
+        } else {
+            1 + count(&text[1..])
+        }
+    });
+    assert_eq!(suffixes.call(&text), 5);
+    assert_eq!(suffixes.call(&text[3..]), 2);
+    assert_eq!(suffixes.stats(), Stats { hits: 1, misses: 6 });
+
+    let mut steps = memoize_dense::<u64, _>(32, |steps, n| match n {
+        0 | 1 => 1,
+        _ => steps(n - 1) + steps(n - 2),
+    });
+    assert_eq!(steps.call(31), 2178309);
+    assert_eq!(steps.memo.len(), 32);
+    assert_eq!(steps.stats().misses, 32);
+
+    let mut store = DenseStore::<u8>::new(4);
+    store.insert(3, 1);
+    store.insert(3, 2);
+    assert_eq!(
+        (store.get(&3), store.get(&4), store.len()),
+        (Some(&2), None, 1)
+    );
+}
@@ -1,2 +1,18 @@
+pub mod answer;
+pub mod bitgrid;
+pub mod checked;
+pub mod config;
+pub mod dot;
+pub mod dsu;
+pub mod extsort;
 pub mod graph;
+pub mod hash;
+pub mod input;
+pub mod math;
+pub mod memo;
+pub mod parse;
 pub mod point;
+pub mod search;
+pub mod sim;
+pub mod span;
+pub mod trace;
@@ -0,0 +1,247 @@
+use std::{fmt::Display, str::FromStr};
+
+/// An error pointing to the place in the input which could not be parsed.
+///
+/// `line` and `column` are 1-based. Helpers working on a single line report line 1;
+/// [`lines`] and [`Section::lines`] move the error to the actual line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    fn at_column(column: usize, message: impl Into<String>) -> Self {
+        Self::new(1, column, message)
+    }
+
+    /// Moves an error reported for a single line to `line` of the input.
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line += line - 1;
+        self
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A block of lines separated from the others by a blank line.
+#[derive(Debug, Clone, Copy)]
+pub struct Section<'a> {
+    /// 1-based line number of the first line in the section.
+    pub first_line: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Section<'a> {
+    /// Parses every line of the section, reporting errors at their line in the whole input.
+    pub fn lines<T>(
+        &self,
+        f: impl FnMut(&'a str) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        lines(self.text, f).map_err(|e| e.at_line(self.first_line))
+    }
+}
+
+/// Splits the input into sections separated by (one or more) blank lines.
+pub fn sections(input: &str) -> Vec<Section<'_>> {
+    let mut sections = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+    let mut offset = 0;
+
+    for (i, line) in input.split_inclusive('\n').enumerate() {
+        let is_blank = line.trim().is_empty();
+
+        match start {
+            None if !is_blank => start = Some((i + 1, offset)),
+            Some((first_line, section_start)) if is_blank => {
+                sections.push(Section {
+                    first_line,
+                    text: &input[section_start..offset],
+                });
+                start = None;
+            }
+            _ => (),
+        }
+
+        offset += line.len();
+    }
+
+    if let Some((first_line, section_start)) = start {
+        sections.push(Section {
+            first_line,
+            text: &input[section_start..],
+        });
+    }
+
+    sections
+}
+
+/// Parses every line of the input with `f`, attaching the line number to errors.
+pub fn lines<'a, T>(
+    input: &'a str,
+    mut f: impl FnMut(&'a str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| f(line).map_err(|e| e.at_line(i + 1)))
+        .collect()
+}
+
+/// Extracts all integers from a line, ignoring any text around them.
+///
+/// A `-` or `+` directly in front of a digit is part of the number, so
+/// `"p=0,4 v=3,-3"` yields `[0, 4, 3, -3]` and `"X+94, Y+34"` yields `[94, 34]`.
+pub fn ints<T: FromStr>(line: &str) -> Result<Vec<T>, ParseError> {
+    let bytes = line.as_bytes();
+    let mut numbers = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let is_sign =
+            matches!(bytes[i], b'-' | b'+') && bytes.get(i + 1).is_some_and(|b| b.is_ascii_digit());
+
+        if is_sign || bytes[i].is_ascii_digit() {
+            let start = i;
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            numbers.push(number(line, start, i)?);
+        } else {
+            i += 1;
+        }
+    }
+
+    Ok(numbers)
+}
+
+/// Parses `line[start..end]`, reporting the column of the number on failure.
+fn number<T: FromStr>(line: &str, start: usize, end: usize) -> Result<T, ParseError> {
+    let text = &line[start..end];
+    text.parse().map_err(|_| {
+        ParseError::at_column(
+            start + 1,
+            format!("`{text}` is not a valid {}", std::any::type_name::<T>()),
+        )
+    })
+}
+
+/// Parses a list of values separated by `separator`, e.g. `"75,47,61"`.
+pub fn list<T: FromStr>(line: &str, separator: char) -> Result<Vec<T>, ParseError> {
+    let mut values = Vec::new();
+    let mut start = 0;
+
+    for part in line.split(separator) {
+        let trimmed_start = start + (part.len() - part.trim_start().len());
+        let trimmed_end = trimmed_start + part.trim().len();
+        values.push(number(line, trimmed_start, trimmed_end)?);
+        start += part.len() + separator.len_utf8();
+    }
+
+    Ok(values)
+}
+
+/// Parses a list of values separated by whitespace, e.g. `"7 6 4 2 1"`.
+pub fn words<T: FromStr>(line: &str) -> Result<Vec<T>, ParseError> {
+    let base = line.as_ptr() as usize;
+
+    line.split_whitespace()
+        .map(|word| {
+            let start = word.as_ptr() as usize - base;
+            number(line, start, start + word.len())
+        })
+        .collect()
+}
+
+/// Parses a `key: values` line, e.g. `"3267: 81 40 27"`.
+pub fn key_values<K: FromStr, V: FromStr>(line: &str) -> Result<(K, Vec<V>), ParseError> {
+    let colon = line
+        .find(':')
+        .ok_or_else(|| ParseError::at_column(1, "expected `key: values`"))?;
+    let key = number(line, 0, colon)?;
+    let values = words(&line[colon + 1..])
+        .map_err(|e| ParseError::at_column(e.column + colon + 1, e.message))?;
+
+    Ok((key, values))
+}
+
+/// Parses a rectangular grid of characters, mapping each one with `f`.
+///
+/// Returns the tiles in row-major order together with the width and height.
+/// `f` returns `None` for characters which are not allowed in the grid.
+pub fn grid<T>(
+    input: &str,
+    mut f: impl FnMut(char) -> Option<T>,
+) -> Result<(Vec<T>, usize, usize), ParseError> {
+    let mut tiles = Vec::new();
+    let mut width = None;
+    let mut height = 0;
+
+    for (y, line) in input.lines().enumerate() {
+        let mut row_width = 0;
+
+        for (x, c) in line.chars().enumerate() {
+            let tile = f(c).ok_or_else(|| {
+                ParseError::new(y + 1, x + 1, format!("unexpected character `{c}` in grid"))
+            })?;
+            tiles.push(tile);
+            row_width += 1;
+        }
+
+        match width {
+            None => width = Some(row_width),
+            Some(width) if width != row_width => {
+                return Err(ParseError::new(
+                    y + 1,
+                    row_width + 1,
+                    format!("row has width {row_width}, expected {width}"),
+                ))
+            }
+            _ => (),
+        }
+
+        height += 1;
+    }
+
+    Ok((tiles, width.unwrap_or(0), height))
+}
+
+/// Parses a rectangular grid of characters into rows.
+pub fn char_grid(input: &str) -> Result<Vec<Vec<char>>, ParseError> {
+    let (tiles, width, _) = grid(input, Some)?;
+
+    Ok(tiles.chunks(width.max(1)).map(|row| row.to_vec()).collect())
+}
+
+#[test]
+fn test_errors_are_located() {
+    let input = "1,2\n\n3,4\n5,x\n";
+    let sections = sections(input);
+    assert_eq!(sections.len(), 2);
+    assert_eq!(sections[1].first_line, 3);
+
+    let error = sections[1]
+        .lines(|line| list::<u32>(line, ','))
+        .unwrap_err();
+    assert_eq!((error.line, error.column), (4, 3));
+
+    assert_eq!(ints::<i32>("p=0,4 v=3,-3"), Ok(vec![0, 4, 3, -3]));
+    assert_eq!(ints::<u32>("X=-3").unwrap_err().column, 3);
+    assert_eq!(key_values::<u64, u64>("190: 10 1a").unwrap_err().column, 9);
+}
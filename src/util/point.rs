@@ -3,6 +3,8 @@ use std::{
     ops::{Add, AddAssign},
 };
 
+// Up and left are `usize::MAX`, which only works because `Add` wraps around
+// on purpose. Out of bounds positions end up huge and fail the bounds checks.
 pub const DIRS: [Point; 4] = [
     Point::new(0, usize::MAX),
     Point::new(1, 0),
@@ -0,0 +1,464 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    fmt::Display,
+    hash::Hash,
+    ops::AddAssign,
+};
+
+use super::{
+    hash::{FastMap, FastSet},
+    memo::Memo,
+    trace::{self, Event, Value},
+};
+
+/// A puzzle described as states connected by weighted moves.
+///
+/// Implementing this trait is enough to use all search algorithms of this module.
+pub trait SearchProblem {
+    type State: Clone + Eq + Hash;
+
+    fn start(&self) -> Vec<Self::State>;
+
+    /// Pushes every state reachable from `state` in one move, with the cost of the move.
+    fn successors(&self, state: &Self::State, next: &mut Vec<(Self::State, usize)>);
+
+    fn is_goal(&self, state: &Self::State) -> bool;
+
+    /// A lower bound for the remaining cost to a goal, used by [`astar`].
+    /// Has to be consistent for [`astar`] and [`all_optimal_paths`] to stay optimal.
+    fn heuristic(&self, _state: &Self::State) -> usize {
+        0
+    }
+}
+
+/// How much work a search did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    /// States taken from the frontier and expanded.
+    pub visited: usize,
+    /// States added to the frontier.
+    pub discovered: usize,
+}
+
+impl Stats {
+    /// Emits the statistics as a trace event, see [`trace`].
+    pub fn report(&self, search: &'static str) {
+        trace::emit(|| Report {
+            search,
+            stats: *self,
+        });
+    }
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        self.visited += other.visited;
+        self.discovered += other.discovered;
+    }
+}
+
+struct Report {
+    search: &'static str,
+    stats: Stats,
+}
+
+impl Event for Report {
+    const KIND: &'static str = "search";
+
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("search", self.search.into()),
+            ("visited", self.stats.visited.into()),
+            ("discovered", self.stats.discovered.into()),
+        ]
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} visited, {} discovered",
+            self.visited, self.discovered
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution<S> {
+    /// All states from a start state to the goal.
+    pub path: Vec<S>,
+    pub cost: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult<S> {
+    pub solution: Option<Solution<S>>,
+    pub stats: Stats,
+}
+
+impl<S> SearchResult<S> {
+    pub fn cost(&self) -> Option<usize> {
+        self.solution.as_ref().map(|solution| solution.cost)
+    }
+}
+
+// Follows the parents back from the goal and sums up the costs of the moves.
+fn reconstruct<S: Clone + Eq + Hash>(parents: &FastMap<S, (S, usize)>, goal: S) -> Solution<S> {
+    let mut path = vec![goal];
+    let mut cost = 0;
+
+    while let Some((parent, move_cost)) = parents.get(path.last().unwrap()) {
+        path.push(parent.clone());
+        cost += move_cost;
+    }
+
+    path.reverse();
+    Solution { path, cost }
+}
+
+/// Breadth first search, finds the path with the fewest moves.
+pub fn bfs<P: SearchProblem>(problem: &P) -> SearchResult<P::State> {
+    let mut stats = Stats::default();
+    let mut parents = FastMap::default();
+    let mut seen = FastSet::default();
+    let mut queue = VecDeque::new();
+    let mut next = Vec::new();
+
+    for start in problem.start() {
+        if seen.insert(start.clone()) {
+            stats.discovered += 1;
+            queue.push_back(start);
+        }
+    }
+
+    while let Some(state) = queue.pop_front() {
+        stats.visited += 1;
+
+        if problem.is_goal(&state) {
+            return SearchResult {
+                solution: Some(reconstruct(&parents, state)),
+                stats,
+            };
+        }
+
+        problem.successors(&state, &mut next);
+        for (successor, cost) in next.drain(..) {
+            if seen.insert(successor.clone()) {
+                stats.discovered += 1;
+                parents.insert(successor.clone(), (state.clone(), cost));
+                queue.push_back(successor);
+            }
+        }
+    }
+
+    SearchResult {
+        solution: None,
+        stats,
+    }
+}
+
+/// Dijkstra's algorithm, finds the cheapest path to a goal.
+pub fn dijkstra<P: SearchProblem>(problem: &P) -> SearchResult<P::State> {
+    best_first(problem, false)
+}
+
+/// A*, like [`dijkstra`] but guided by [`SearchProblem::heuristic`].
+pub fn astar<P: SearchProblem>(problem: &P) -> SearchResult<P::State> {
+    best_first(problem, true)
+}
+
+fn best_first<P: SearchProblem>(problem: &P, use_heuristic: bool) -> SearchResult<P::State> {
+    let mut stats = Stats::default();
+    let mut frontier = Frontier::new(problem, use_heuristic);
+    let mut parents = FastMap::default();
+    let mut closed = FastSet::default();
+    let mut next = Vec::new();
+
+    for start in problem.start() {
+        frontier.push(start, 0, &mut stats);
+    }
+
+    while let Some((state, cost)) = frontier.pop() {
+        if !closed.insert(state.clone()) {
+            continue;
+        }
+        stats.visited += 1;
+
+        if problem.is_goal(&state) {
+            return SearchResult {
+                solution: Some(reconstruct(&parents, state)),
+                stats,
+            };
+        }
+
+        problem.successors(&state, &mut next);
+        for (successor, move_cost) in next.drain(..) {
+            let next_cost = cost + move_cost;
+            if !closed.contains(&successor) && frontier.improves(&successor, next_cost) {
+                parents.insert(successor.clone(), (state.clone(), move_cost));
+                frontier.push(successor, next_cost, &mut stats);
+            }
+        }
+    }
+
+    SearchResult {
+        solution: None,
+        stats,
+    }
+}
+
+/// Priority queue of states ordered by cost (plus heuristic), which remembers the best
+/// known cost of every state so stale entries can be skipped.
+struct Frontier<'a, P: SearchProblem> {
+    problem: &'a P,
+    use_heuristic: bool,
+    heap: BinaryHeap<Reverse<(usize, usize, usize)>>,
+    states: Vec<P::State>,
+    costs: FastMap<P::State, usize>,
+}
+
+impl<'a, P: SearchProblem> Frontier<'a, P> {
+    fn new(problem: &'a P, use_heuristic: bool) -> Self {
+        Self {
+            problem,
+            use_heuristic,
+            heap: BinaryHeap::new(),
+            states: Vec::new(),
+            costs: FastMap::default(),
+        }
+    }
+
+    fn improves(&self, state: &P::State, cost: usize) -> bool {
+        self.costs.get(state).is_none_or(|known| cost < *known)
+    }
+
+    fn cost(&self, state: &P::State) -> Option<usize> {
+        self.costs.get(state).copied()
+    }
+
+    fn push(&mut self, state: P::State, cost: usize, stats: &mut Stats) {
+        let priority = if self.use_heuristic {
+            cost + self.problem.heuristic(&state)
+        } else {
+            cost
+        };
+
+        stats.discovered += 1;
+        self.costs.insert(state.clone(), cost);
+        self.heap.push(Reverse((priority, cost, self.states.len())));
+        self.states.push(state);
+    }
+
+    fn pop(&mut self) -> Option<(P::State, usize)> {
+        while let Some(Reverse((_, cost, index))) = self.heap.pop() {
+            let state = &self.states[index];
+            if self.costs.get(state) == Some(&cost) {
+                return Some((state.clone(), cost));
+            }
+        }
+
+        None
+    }
+}
+
+/// Every optimal path to a goal, stored as the optimal predecessors of each state.
+#[derive(Debug, Clone)]
+pub struct OptimalPaths<S> {
+    /// The goals which can be reached with the lowest cost.
+    pub goals: Vec<S>,
+    pub predecessors: FastMap<S, Vec<S>>,
+    pub stats: Stats,
+}
+
+impl<S: Clone + Eq + Hash> OptimalPaths<S> {
+    /// All moves `(from, to)` which lie on at least one optimal path.
+    pub fn moves(&self) -> FastSet<(S, S)> {
+        let mut moves = FastSet::default();
+        let mut stack = self.goals.clone();
+
+        while let Some(state) = stack.pop() {
+            for predecessor in self.predecessors.get(&state).into_iter().flatten() {
+                if moves.insert((predecessor.clone(), state.clone())) {
+                    stack.push(predecessor.clone());
+                }
+            }
+        }
+
+        moves
+    }
+}
+
+/// Like [`dijkstra`], but keeps going until every optimal path to a goal is known.
+pub fn all_optimal_paths<P: SearchProblem>(problem: &P) -> Option<OptimalPaths<P::State>> {
+    let mut stats = Stats::default();
+    let mut frontier = Frontier::new(problem, true);
+    let mut predecessors: FastMap<P::State, Vec<P::State>> = FastMap::default();
+    let mut closed = FastSet::default();
+    let mut goals = Vec::new();
+    let mut best = None;
+    let mut next = Vec::new();
+
+    for start in problem.start() {
+        frontier.push(start, 0, &mut stats);
+    }
+
+    while let Some((state, cost)) = frontier.pop() {
+        if best.is_some_and(|best| cost > best) {
+            break;
+        } else if !closed.insert(state.clone()) {
+            continue;
+        }
+        stats.visited += 1;
+
+        if problem.is_goal(&state) {
+            best = Some(cost);
+            goals.push(state);
+            continue;
+        }
+
+        problem.successors(&state, &mut next);
+        for (successor, move_cost) in next.drain(..) {
+            let next_cost = cost + move_cost;
+
+            match frontier.cost(&successor) {
+                Some(known) if next_cost == known => {
+                    predecessors
+                        .entry(successor)
+                        .or_default()
+                        .push(state.clone());
+                }
+                Some(known) if next_cost > known => (),
+                _ => {
+                    predecessors.insert(successor.clone(), vec![state.clone()]);
+                    frontier.push(successor, next_cost, &mut stats);
+                }
+            }
+        }
+    }
+
+    best.map(|_| OptimalPaths {
+        goals,
+        predecessors,
+        stats,
+    })
+}
+
+/// Exhaustive depth first search, returns every reachable goal.
+pub fn reachable_goals<P: SearchProblem>(problem: &P) -> (Vec<P::State>, Stats) {
+    let mut stats = Stats::default();
+    let mut seen = FastSet::default();
+    let mut goals = Vec::new();
+    let mut stack = problem.start();
+    let mut next = Vec::new();
+    stats.discovered += stack.len();
+
+    while let Some(state) = stack.pop() {
+        if !seen.insert(state.clone()) {
+            continue;
+        }
+        stats.visited += 1;
+
+        if problem.is_goal(&state) {
+            goals.push(state.clone());
+        }
+
+        problem.successors(&state, &mut next);
+        for (successor, _) in next.drain(..) {
+            if !seen.contains(&successor) {
+                stats.discovered += 1;
+                stack.push(successor);
+            }
+        }
+    }
+
+    (goals, stats)
+}
+
+/// Counts the distinct paths from the start states to any goal. Paths end at the first
+/// goal they reach. The moves must not form cycles.
+pub fn count_paths<P: SearchProblem>(problem: &P) -> usize {
+    let mut memo = Memo::new();
+
+    problem
+        .start()
+        .into_iter()
+        .map(|start| count_paths_from(problem, start, &mut memo))
+        .sum()
+}
+
+fn count_paths_from<P: SearchProblem>(
+    problem: &P,
+    state: P::State,
+    memo: &mut Memo<P::State, usize>,
+) -> usize {
+    memo.get_or_insert_with(state.clone(), |memo| {
+        if problem.is_goal(&state) {
+            1
+        } else {
+            let mut next = Vec::new();
+            problem.successors(&state, &mut next);
+            next.into_iter()
+                .map(|(successor, _)| count_paths_from(problem, successor, memo))
+                .sum()
+        }
+    })
+}
+
+#[cfg(test)]
+struct OpenGrid {
+    size: usize,
+}
+
+#[cfg(test)]
+impl SearchProblem for OpenGrid {
+    type State = (usize, usize);
+
+    fn start(&self) -> Vec<(usize, usize)> {
+        vec![(0, 0)]
+    }
+
+    fn successors(&self, &(x, y): &(usize, usize), next: &mut Vec<((usize, usize), usize)>) {
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        next.extend(
+            neighbours
+                .into_iter()
+                .filter(|(x, y)| *x < self.size && *y < self.size)
+                .map(|point| (point, 1)),
+        );
+    }
+
+    fn is_goal(&self, state: &(usize, usize)) -> bool {
+        *state == (self.size - 1, self.size - 1)
+    }
+
+    fn heuristic(&self, &(x, y): &(usize, usize)) -> usize {
+        2 * (self.size - 1) - x - y
+    }
+}
+
+#[test]
+fn test_search() {
+    let grid = OpenGrid { size: 3 };
+
+    for result in [bfs(&grid), dijkstra(&grid), astar(&grid)] {
+        let solution = result.solution.unwrap();
+        assert_eq!(solution.path.first(), Some(&(0, 0)));
+        assert_eq!(solution.path.last(), Some(&(2, 2)));
+        assert_eq!(solution.cost, solution.path.len() - 1);
+    }
+    assert_eq!(bfs(&grid).cost(), Some(4));
+    assert!(astar(&grid).stats.visited <= dijkstra(&grid).stats.visited);
+
+    let optimal = all_optimal_paths(&grid).unwrap();
+    assert_eq!(optimal.goals, [(2, 2)]);
+    // Every move right or down lies on an optimal path.
+    assert_eq!(optimal.moves().len(), 12);
+    assert_eq!(count_paths(&OpenGrid { size: 1 }), 1);
+}
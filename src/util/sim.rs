@@ -0,0 +1,309 @@
+/// Something that evolves in discrete steps, like a guard walking through a lab or
+/// robots moving around a room.
+pub trait Simulation {
+    /// Advances the simulation by one step. Returns false if it is over and
+    /// nothing changed.
+    fn step(&mut self) -> bool;
+
+    /// Runs up to `n` steps and returns how many were taken before the simulation ended.
+    fn run_n(&mut self, n: usize) -> usize {
+        for i in 0..n {
+            if !self.step() {
+                return i;
+            }
+        }
+
+        n
+    }
+
+    /// Steps until `done` holds and returns the number of steps taken,
+    /// or `None` if the simulation ended first.
+    #[cfg(test)]
+    fn run_until(&mut self, mut done: impl FnMut(&Self) -> bool) -> Option<usize>
+    where
+        Self: Sized,
+    {
+        let mut steps = 0;
+
+        while !done(self) {
+            if !self.step() {
+                return None;
+            }
+            steps += 1;
+        }
+
+        Some(steps)
+    }
+
+    /// Steps until the simulation ends and returns the number of steps taken.
+    /// Never returns if the simulation runs in a cycle.
+    fn run_to_end(&mut self) -> usize {
+        let mut steps = 0;
+
+        while self.step() {
+            steps += 1;
+        }
+
+        steps
+    }
+}
+
+/// A simulation whose states can be compared and saved, which is what finding
+/// its cycle takes.
+///
+/// Cycle detection compares fingerprints, so two states with the same fingerprint
+/// have to behave the same from then on.
+pub trait Periodic: Simulation {
+    type Fingerprint: Eq;
+    type Snapshot;
+
+    fn fingerprint(&self) -> Self::Fingerprint;
+
+    /// Captures everything [`Simulation::step`] changes, so it can be restored later.
+    fn snapshot(&self) -> Self::Snapshot;
+
+    fn restore(&mut self, snapshot: &Self::Snapshot);
+
+    /// Finds the cycle the simulation eventually runs into with Brent's algorithm,
+    /// or returns `None` if it ends before. A fixed point is a cycle of length 1.
+    ///
+    /// Leaves the simulation in the state it was in before the call.
+    fn find_cycle(&mut self) -> Option<Cycle>
+    where
+        Self: Sized,
+    {
+        let initial = self.snapshot();
+        let cycle = brent(self, &initial);
+        self.restore(&initial);
+        cycle
+    }
+
+    /// Like [`Periodic::find_cycle`], but with Floyd's algorithm. Only used to check
+    /// the results of Brent's.
+    #[cfg(test)]
+    fn find_cycle_floyd(&mut self) -> Option<Cycle>
+    where
+        Self: Sized,
+    {
+        let initial = self.snapshot();
+        let cycle = floyd(self, &initial);
+        self.restore(&initial);
+        cycle
+    }
+}
+
+/// States repeat every `length` steps, starting after `start` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// The first step which has the same state as step `n`. Useful to skip ahead
+    /// to a step far in the future.
+    pub fn equivalent_step(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.length
+        }
+    }
+}
+
+// Both algorithms need two positions in the simulation at once, which are kept as
+// snapshots and restored in turn.
+struct Cursor<S: Periodic> {
+    snapshot: S::Snapshot,
+    fingerprint: S::Fingerprint,
+}
+
+impl<S: Periodic> Cursor<S> {
+    fn new(sim: &S) -> Self {
+        Self {
+            snapshot: sim.snapshot(),
+            fingerprint: sim.fingerprint(),
+        }
+    }
+
+    fn advance(&mut self, sim: &mut S, steps: usize) -> bool {
+        sim.restore(&self.snapshot);
+        if sim.run_n(steps) < steps {
+            return false;
+        }
+
+        *self = Self::new(sim);
+        true
+    }
+}
+
+fn brent<S: Periodic>(sim: &mut S, initial: &S::Snapshot) -> Option<Cycle> {
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = sim.fingerprint();
+
+    if !sim.step() {
+        return None;
+    }
+
+    while sim.fingerprint() != tortoise {
+        if power == length {
+            tortoise = sim.fingerprint();
+            power *= 2;
+            length = 0;
+        }
+
+        if !sim.step() {
+            return None;
+        }
+        length += 1;
+    }
+
+    sim.restore(initial);
+    let start = cycle_start(sim, length);
+
+    Some(Cycle { start, length })
+}
+
+#[cfg(test)]
+fn floyd<S: Periodic>(sim: &mut S, initial: &S::Snapshot) -> Option<Cycle> {
+    let mut tortoise = Cursor::new(sim);
+    let mut hare = Cursor::new(sim);
+
+    loop {
+        if !tortoise.advance(sim, 1) || !hare.advance(sim, 2) {
+            return None;
+        }
+        if tortoise.fingerprint == hare.fingerprint {
+            break;
+        }
+    }
+
+    let mut length = 1;
+    let mut runner = Cursor::new(sim);
+    runner.advance(sim, 1);
+    while runner.fingerprint != hare.fingerprint {
+        runner.advance(sim, 1);
+        length += 1;
+    }
+
+    sim.restore(initial);
+    let start = cycle_start(sim, length);
+
+    Some(Cycle { start, length })
+}
+
+// Moves one cursor `length` steps ahead of the other and advances both until they meet,
+// which happens at the start of the cycle.
+fn cycle_start<S: Periodic>(sim: &mut S, length: usize) -> usize {
+    let mut tortoise = Cursor::new(sim);
+    let mut hare = Cursor::new(sim);
+    hare.advance(sim, length);
+    let mut start = 0;
+
+    while tortoise.fingerprint != hare.fingerprint {
+        tortoise.advance(sim, 1);
+        hare.advance(sim, 1);
+        start += 1;
+    }
+
+    start
+}
+
+#[cfg(test)]
+struct Sequence {
+    value: u64,
+}
+
+#[cfg(test)]
+impl Simulation for Sequence {
+    fn step(&mut self) -> bool {
+        self.value = (self.value * self.value + 1) % 255;
+        true
+    }
+}
+
+#[cfg(test)]
+impl Periodic for Sequence {
+    type Fingerprint = u64;
+    type Snapshot = u64;
+
+    fn fingerprint(&self) -> u64 {
+        self.value
+    }
+
+    fn snapshot(&self) -> u64 {
+        self.value
+    }
+
+    fn restore(&mut self, snapshot: &u64) {
+        self.value = *snapshot;
+    }
+}
+
+#[test]
+fn test_find_cycle() {
+    // 0, 1, 2, 5, 26, 167, 95, 101 and then back to 2.
+    let mut sequence = Sequence { value: 0 };
+    let cycle = Cycle {
+        start: 2,
+        length: 6,
+    };
+    assert_eq!(sequence.find_cycle(), Some(cycle));
+    assert_eq!(sequence.find_cycle_floyd(), Some(cycle));
+    assert_eq!(cycle.equivalent_step(14), 2);
+
+    for value in 0..255 {
+        let mut sequence = Sequence { value };
+
+        let mut seen = Vec::new();
+        while !seen.contains(&sequence.value) {
+            seen.push(sequence.value);
+            sequence.step();
+        }
+        let start = seen.iter().position(|v| *v == sequence.value).unwrap();
+        let expected = Cycle {
+            start,
+            length: seen.len() - start,
+        };
+
+        let mut sequence = Sequence { value };
+        assert_eq!(sequence.find_cycle(), Some(expected));
+        assert_eq!(sequence.find_cycle_floyd(), Some(expected));
+        assert_eq!(sequence.value, value);
+
+        sequence.run_n(1000);
+        let equivalent = expected.equivalent_step(1000);
+        assert_eq!(sequence.value, seen[equivalent]);
+    }
+}
+
+#[cfg(test)]
+struct Countdown {
+    value: u64,
+}
+
+#[cfg(test)]
+impl Simulation for Countdown {
+    fn step(&mut self) -> bool {
+        if self.value == 0 {
+            false
+        } else {
+            self.value -= 1;
+            true
+        }
+    }
+}
+
+#[test]
+fn test_run() {
+    let mut countdown = Countdown { value: 10 };
+    assert_eq!(countdown.run_n(4), 4);
+    assert_eq!(countdown.value, 6);
+    assert_eq!(countdown.run_n(10), 6);
+    assert_eq!(countdown.value, 0);
+
+    let mut countdown = Countdown { value: 7 };
+    assert_eq!(countdown.run_to_end(), 7);
+    assert_eq!(countdown.run_to_end(), 0);
+}
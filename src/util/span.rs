@@ -0,0 +1,214 @@
+//! Timing of the phases inside a solver, e.g. parsing and searching.
+//!
+//! `let _s = span("build_graph");` measures until the end of the scope. Spans opened while
+//! another one is active become its children, and spans with the same name and parent are
+//! added up. Without the `spans` feature, spans are zero sized and do nothing.
+
+use std::time::Duration;
+
+/// A measured phase with all its nested phases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phase {
+    pub name: &'static str,
+    pub duration: Duration,
+    /// How often the span was entered.
+    pub calls: usize,
+    pub children: Vec<Phase>,
+}
+
+impl Phase {
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"name":"{}","micros":{},"calls":{},"phases":{}}}"#,
+            self.name,
+            self.duration.as_micros(),
+            self.calls,
+            phases_to_json(&self.children)
+        )
+    }
+}
+
+pub fn phases_to_json(phases: &[Phase]) -> String {
+    let phases: Vec<_> = phases.iter().map(Phase::to_json).collect();
+    format!("[{}]", phases.join(","))
+}
+
+/// Writes the phases as an indented table, one line per phase.
+pub fn write_table(
+    out: &mut impl std::fmt::Write,
+    phases: &[Phase],
+    depth: usize,
+) -> std::fmt::Result {
+    for phase in phases {
+        write!(
+            out,
+            "{:indent$}{:<name_width$}{:>10}us",
+            "",
+            phase.name,
+            phase.duration.as_micros(),
+            indent = 8 + depth * 2,
+            name_width = 24 - depth * 2
+        )?;
+        if phase.calls > 1 {
+            write!(out, " ({} calls)", phase.calls)?;
+        }
+        writeln!(out)?;
+        write_table(out, &phase.children, depth + 1)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "spans")]
+mod recorder {
+    use std::{cell::RefCell, time::Instant};
+
+    use super::Phase;
+
+    struct Node {
+        phase: Phase,
+        parent: Option<usize>,
+        children: Vec<usize>,
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        nodes: Vec<Node>,
+        roots: Vec<usize>,
+        current: Option<usize>,
+    }
+
+    thread_local! {
+        static RECORDER: RefCell<Recorder> = RefCell::default();
+    }
+
+    /// Measures the time until it is dropped.
+    #[must_use = "the span ends when this is dropped"]
+    pub struct Span {
+        node: usize,
+        start: Instant,
+    }
+
+    pub fn span(name: &'static str) -> Span {
+        let node = RECORDER.with_borrow_mut(|recorder| {
+            let siblings = match recorder.current {
+                Some(parent) => &recorder.nodes[parent].children,
+                None => &recorder.roots,
+            };
+
+            let existing = siblings
+                .iter()
+                .copied()
+                .find(|node| recorder.nodes[*node].phase.name == name);
+
+            let node = existing.unwrap_or_else(|| {
+                let node = recorder.nodes.len();
+                recorder.nodes.push(Node {
+                    phase: Phase {
+                        name,
+                        duration: Default::default(),
+                        calls: 0,
+                        children: Vec::new(),
+                    },
+                    parent: recorder.current,
+                    children: Vec::new(),
+                });
+                match recorder.current {
+                    Some(parent) => recorder.nodes[parent].children.push(node),
+                    None => recorder.roots.push(node),
+                }
+                node
+            });
+
+            recorder.nodes[node].phase.calls += 1;
+            recorder.current = Some(node);
+            node
+        });
+
+        Span {
+            node,
+            start: Instant::now(),
+        }
+    }
+
+    impl Drop for Span {
+        fn drop(&mut self) {
+            let elapsed = self.start.elapsed();
+
+            RECORDER.with_borrow_mut(|recorder| {
+                let node = &mut recorder.nodes[self.node];
+                node.phase.duration += elapsed;
+                recorder.current = node.parent;
+            });
+        }
+    }
+
+    pub fn take() -> Vec<Phase> {
+        fn build(nodes: &[Node], node: usize) -> Phase {
+            let mut phase = nodes[node].phase.clone();
+            phase.children = nodes[node]
+                .children
+                .iter()
+                .map(|child| build(nodes, *child))
+                .collect();
+            phase
+        }
+
+        RECORDER.with_borrow_mut(|recorder| {
+            let recorder = std::mem::take(recorder);
+            recorder
+                .roots
+                .iter()
+                .map(|root| build(&recorder.nodes, *root))
+                .collect()
+        })
+    }
+}
+
+#[cfg(not(feature = "spans"))]
+mod recorder {
+    use super::Phase;
+
+    pub struct Span;
+
+    #[inline(always)]
+    pub fn span(_name: &'static str) -> Span {
+        Span
+    }
+
+    pub fn take() -> Vec<Phase> {
+        Vec::new()
+    }
+}
+
+pub use recorder::span;
+
+/// Returns the phases measured on this thread since the last call and starts over.
+pub fn take() -> Vec<Phase> {
+    recorder::take()
+}
+
+#[cfg(feature = "spans")]
+#[test]
+fn test_nested_spans() {
+    take();
+    {
+        let _outer = span("outer");
+        for _ in 0..3 {
+            let _inner = span("inner");
+        }
+    }
+    let _other = span("other");
+    drop(_other);
+
+    let phases = take();
+    assert_eq!(phases.len(), 2);
+    assert_eq!((phases[0].name, phases[0].calls), ("outer", 1));
+    assert_eq!(
+        (phases[0].children[0].name, phases[0].children[0].calls),
+        ("inner", 3)
+    );
+    assert!(phases[0].duration >= phases[0].children[0].duration);
+    assert_eq!(phases[1].name, "other");
+    assert!(take().is_empty());
+}
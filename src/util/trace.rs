@@ -0,0 +1,257 @@
+//! An opt-in channel for solvers to explain what they did.
+//!
+//! Days define their own event types and emit them with `trace::emit(|| ...)`.
+//! The closure only runs while tracing is enabled (`--explain`), so a normal run
+//! pays a single relaxed atomic load per call.
+
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use super::answer::json_string;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static EVENTS: RefCell<Vec<Record>> = const { RefCell::new(Vec::new()) };
+}
+
+// Tests run in parallel, so they only enable tracing for their own thread.
+#[cfg(test)]
+thread_local! {
+    static ENABLED_HERE: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// A field value of an event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    None,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Text(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn to_json(&self) -> String {
+        match self {
+            Value::None => "null".to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Int(n) => n.to_string(),
+            Value::UInt(n) => n.to_string(),
+            Value::Text(text) => json_string(text),
+            Value::List(values) => {
+                let values: Vec<_> = values.iter().map(Value::to_json).collect();
+                format!("[{}]", values.join(","))
+            }
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::None => write!(f, "-"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Int(n) => write!(f, "{n}"),
+            Value::UInt(n) => write!(f, "{n}"),
+            Value::Text(text) => write!(f, "{text}"),
+            Value::List(values) => {
+                let values: Vec<_> = values.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", values.join(" "))
+            }
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+macro_rules! impl_from_int {
+    ( $variant:ident, $as:ty: $( $t:ty ),* ) => {
+        $(
+            impl From<$t> for Value {
+                fn from(value: $t) -> Self {
+                    Value::$variant(value as $as)
+                }
+            }
+        )*
+    };
+}
+
+// Both are widened without loss, `usize` and `isize` have at most 64 bits.
+impl_from_int!(Int, i64: i8, i16, i32, i64, isize);
+impl_from_int!(UInt, u64: u8, u16, u32, u64, usize);
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<char> for Value {
+    fn from(value: char) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::None, Into::into)
+    }
+}
+
+impl<T: Clone + Into<Value>> From<&[T]> for Value {
+    fn from(values: &[T]) -> Self {
+        Value::List(values.iter().cloned().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Something a solver can report about its work.
+pub trait Event {
+    const KIND: &'static str;
+
+    fn fields(&self) -> Vec<(&'static str, Value)>;
+}
+
+/// An emitted event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub kind: &'static str,
+    pub fields: Vec<(&'static str, Value)>,
+}
+
+impl Record {
+    pub fn to_json(&self) -> String {
+        let mut json = format!(r#"{{"kind":"{}""#, self.kind);
+        for (name, value) in &self.fields {
+            json.push_str(&format!(r#","{name}":{}"#, value.to_json()));
+        }
+        json.push('}');
+        json
+    }
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+        for (name, value) in &self.fields {
+            write!(f, " {name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn is_enabled() -> bool {
+    #[cfg(test)]
+    if ENABLED_HERE.get() {
+        return true;
+    }
+
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Enables tracing on the current thread until the returned guard is dropped.
+#[cfg(test)]
+pub fn enable_here() -> EnabledHere {
+    ENABLED_HERE.set(true);
+    EnabledHere
+}
+
+#[cfg(test)]
+pub struct EnabledHere;
+
+#[cfg(test)]
+impl Drop for EnabledHere {
+    fn drop(&mut self) {
+        ENABLED_HERE.set(false);
+    }
+}
+
+/// Records the event built by `event` if tracing is enabled.
+#[inline]
+pub fn emit<E: Event>(event: impl FnOnce() -> E) {
+    if is_enabled() {
+        let event = event();
+        let record = Record {
+            kind: E::KIND,
+            fields: event.fields(),
+        };
+        EVENTS.with_borrow_mut(|events| events.push(record));
+    }
+}
+
+/// Returns the events emitted on this thread since the last call.
+pub fn take() -> Vec<Record> {
+    EVENTS.with_borrow_mut(std::mem::take)
+}
+
+#[test]
+fn test_emit() {
+    struct Checked {
+        report: Vec<u8>,
+        safe: bool,
+        total: u64,
+    }
+
+    impl Event for Checked {
+        const KIND: &'static str = "checked";
+
+        fn fields(&self) -> Vec<(&'static str, Value)> {
+            vec![
+                ("report", self.report.clone().into()),
+                ("safe", self.safe.into()),
+                ("total", self.total.into()),
+            ]
+        }
+    }
+
+    let checked = || Checked {
+        report: vec![1, 2],
+        safe: true,
+        total: u64::MAX,
+    };
+
+    take();
+    emit(checked);
+    assert!(take().is_empty());
+
+    let enabled = enable_here();
+    emit(checked);
+    drop(enabled);
+    emit(checked);
+
+    let events = take();
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].to_string(),
+        "checked report=[1 2] safe=true total=18446744073709551615"
+    );
+    assert_eq!(
+        events[0].to_json(),
+        r#"{"kind":"checked","report":[1,2],"safe":true,"total":18446744073709551615}"#
+    );
+}